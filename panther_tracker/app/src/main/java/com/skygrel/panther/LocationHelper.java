@@ -20,7 +20,9 @@ public class LocationHelper {
             @Override
             public void onLocationChanged(Location location) {
                 // Call a static native method without nativePtr
-                onLocationUpdate(location.getLatitude(), location.getLongitude(), location.getAccuracy(), location.getElapsedRealtimeNanos() / 1_000_000_000.0);
+                // UTC time of the fix, stored with the track. NaN altitude means the fix has none
+                double altitude = location.hasAltitude() ? location.getAltitude() : Double.NaN;
+                onLocationUpdate(location.getLatitude(), location.getLongitude(), location.getAccuracy(), location.getTime() / 1_000.0, altitude);
 //                Log.i("GPS", "Location info: ACC: " + location.getAccuracy() + ", altitude: " +
 //                        location.getAltitude() + " +-" + location.getVerticalAccuracyMeters() +
 //                        "\nTime: " + location.getElapsedRealtimeNanos() / 1000000000 +
@@ -72,7 +74,7 @@ public class LocationHelper {
     }
 
     // Modified to not use nativePtr
-    private native void onLocationUpdate(double latitude, double longitude, double acc, double timestamp);
+    private native void onLocationUpdate(double latitude, double longitude, double acc, double timestamp, double altitude);
 
     public native void onPermissionDenied();
     public native void onPermissionGranted();
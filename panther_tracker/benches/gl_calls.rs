@@ -0,0 +1,87 @@
+// Counts GL calls per frame of every screen, rendered offscreen.
+//
+//   cargo bench --target x86_64-unknown-linux-gnu --features headless --bench gl_calls
+//
+// Frame times are of llvmpipe, only compare them between runs on the same machine.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+use panther::render::headless::{gl_calls, reset_gl_calls, HeadlessRenderer};
+use panther::render::screens::active_training::ActiveTrainingScreen;
+use panther::render::screens::main::MainScreen;
+use panther::render::screens::paused_screen::PausedScreen;
+use panther::render::screens::records::RecordsScreen;
+use panther::render::screens::settings::SettingsScreen;
+use panther::render::screens::stats::StatsScreen;
+use panther::render::screens::ScreenTrait;
+
+const DIMS: (u32, u32) = (360, 720);
+/// Screens are animated in and their glyphs are rasterized before counting
+const WARMUP_FRAMES: u32 = 90;
+const FRAMES: u32 = 60;
+/// Functions listed for every screen
+const TOP_CALLS: usize = 6;
+
+const DRAW_CALLS: [&str; 2] = ["DrawArrays", "DrawElements"];
+
+fn bench_screen(renderer: &mut HeadlessRenderer, name: &str, screens: &mut [&mut dyn ScreenTrait]) {
+    renderer.draw_frames(screens, WARMUP_FRAMES);
+
+    reset_gl_calls();
+    let start = Instant::now();
+    renderer.draw_frames(screens, FRAMES);
+    // waits for llvmpipe to finish the frames
+    renderer.read_pixels();
+    let elapsed = start.elapsed();
+
+    // without the read back
+    let calls: Vec<_> = gl_calls().into_iter()
+        .filter(|(name, _)| !matches!(*name, "ReadPixels" | "PixelStorei"))
+        .collect();
+    let per_frame = |count: u64| count as f64 / FRAMES as f64;
+    let total: u64 = calls.iter().map(|(_, count)| count).sum();
+    let draws: u64 = calls.iter()
+        .filter(|(name, _)| DRAW_CALLS.contains(name))
+        .map(|(_, count)| count)
+        .sum();
+
+    println!("{:<20} {:>8.1} calls {:>6.1} draws {:>8.2} ms/frame", name,
+             per_frame(total), per_frame(draws), elapsed.as_secs_f64() * 1000.0 / FRAMES as f64);
+    let top: Vec<_> = calls.iter()
+        .take(TOP_CALLS)
+        .map(|(name, count)| format!("{} {:.1}", name, per_frame(*count)))
+        .collect();
+    println!("{:<20} {}", "", top.join(", "));
+}
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Warn)
+        .parse_default_env()
+        .init();
+
+    let mut renderer = HeadlessRenderer::new(DIMS).expect("Failed to create headless renderer");
+    let gl = renderer.gl();
+    let exit_request = Arc::new(AtomicBool::new(false));
+
+    println!("{} frames of {}x{}, per frame:", FRAMES, DIMS.0, DIMS.1);
+
+    let mut main_screen = MainScreen::new(gl.clone(), exit_request);
+    bench_screen(&mut renderer, "main", &mut [&mut main_screen]);
+
+    let mut records_screen = RecordsScreen::new(gl.clone());
+    bench_screen(&mut renderer, "records", &mut [&mut records_screen]);
+
+    let mut stats_screen = StatsScreen::new(gl.clone());
+    bench_screen(&mut renderer, "stats", &mut [&mut stats_screen]);
+
+    let mut settings_screen = SettingsScreen::new(gl.clone());
+    bench_screen(&mut renderer, "settings", &mut [&mut settings_screen]);
+
+    let mut active_training_screen = ActiveTrainingScreen::new(gl.clone());
+    bench_screen(&mut renderer, "active_training", &mut [&mut active_training_screen]);
+
+    let mut paused_screen = PausedScreen::new(gl.clone());
+    bench_screen(&mut renderer, "paused", &mut [&mut active_training_screen, &mut paused_screen]);
+}
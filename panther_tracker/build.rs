@@ -1,13 +1,47 @@
 use std::env;
 use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
 
 use gl_generator::{Api, Fallbacks, Profile, Registry, StructGenerator};
 
 fn main() {
     let dest = PathBuf::from(&env::var("OUT_DIR").unwrap());
-    let mut file = File::create(dest.join("gl_bindings.rs")).unwrap();
+    let mut bindings = vec![];
     Registry::new(Api::Gles2, (3, 2), Profile::Core, Fallbacks::All, [])
-        .write_bindings(StructGenerator, &mut file)
+        .write_bindings(StructGenerator, &mut bindings)
         .unwrap();
+    let mut bindings = String::from_utf8(bindings).unwrap();
+
+    if env::var_os("CARGO_FEATURE_HEADLESS").is_some() {
+        bindings = count_calls(&bindings);
+    }
+
+    let mut file = File::create(dest.join("gl_bindings.rs")).unwrap();
+    file.write_all(bindings.as_bytes()).unwrap();
+}
+
+/// Makes every GL function bump its counter, see `headless::gl_calls`
+fn count_calls(bindings: &str) -> String {
+    const FN_START: &str = "#[inline] pub unsafe fn ";
+    const BODY_START: &str = "{ __gl_imports::mem::transmute";
+
+    let mut names = vec![];
+    let mut result = String::with_capacity(bindings.len() * 2);
+    for line in bindings.lines() {
+        match line.find(FN_START) {
+            Some(start) if line.contains(BODY_START) => {
+                let name_start = start + FN_START.len();
+                let name_end = name_start + line[name_start..].find('(').unwrap();
+                let body = format!("{{ count_call({}); __gl_imports::mem::transmute", names.len());
+                names.push(line[name_start..name_end].to_string());
+                result.push_str(&line.replacen(BODY_START, &body, 1));
+            }
+            _ => result.push_str(line),
+        }
+        result.push('\n');
+    }
+
+    result.push_str(&format!("pub static CALL_NAMES: [&str; {}] = {:?};\n", names.len(), names));
+    result
 }
@@ -0,0 +1,4 @@
+#!/bin/bash
+
+# PANTHER_ROUTE=route.gpx PANTHER_ROUTE_SPEED=10 ./run_desktop.sh
+cargo run --target x86_64-unknown-linux-gnu --features desktop --bin test-winit-glutin
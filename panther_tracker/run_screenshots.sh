@@ -0,0 +1,10 @@
+#!/bin/bash
+
+# Compares every screen with golden/*.png, pass --update to accept the new rendering.
+# Needs EGL, Mesa's llvmpipe is enough.
+if [ "$1" == "--update" ]; then
+    shift
+    cargo run --target x86_64-unknown-linux-gnu --features headless --bin screenshots -- "$@"
+else
+    cargo test --target x86_64-unknown-linux-gnu --features headless --test golden -- "$@"
+fi
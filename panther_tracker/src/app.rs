@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -13,7 +14,6 @@ use raw_window_handle::{HasRawWindowHandle, RawDisplayHandle, RawWindowHandle};
 use winit::dpi::PhysicalPosition;
 use winit::event_loop::EventLoopWindowTarget;
 use crate::render::{AppState, get_surface_y_ratio, SURFACE_WIDTH};
-use crate::render::screens::ScreenManagementCmd;
 
 
 struct SurfaceState {
@@ -41,13 +41,13 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(winsys_display: RawDisplayHandle, exit_request: Arc<AtomicBool>) -> Self {
+    pub fn new(winsys_display: RawDisplayHandle, exit_request: Arc<AtomicBool>, data_dir: PathBuf) -> Self {
         Self {
             winsys_display,
             glutin_display: None,
             surface_state: None,
             context: None,
-            app_state: AppState::new(exit_request.clone()),
+            app_state: AppState::new(exit_request.clone(), data_dir),
             exit_request,
             touch_state: BTreeMap::new(),
             surface_dims: (0, 0)
@@ -81,13 +81,12 @@ impl App {
         raw_window_handle: RawWindowHandle,
         config: &Config,
     ) -> NotCurrentContext {
-        let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
-
-        // Since glutin by default tries to create OpenGL core context, which may not be
-        // present we should try gles.
-        let fallback_context_attributes = ContextAttributesBuilder::new()
+        // Shaders are GLSL ES 3.0, so GLES is preferred. Desktop drivers without GLES
+        // fall back to OpenGL, which accepts them through ARB_ES3_compatibility.
+        let context_attributes = ContextAttributesBuilder::new()
             .with_context_api(ContextApi::Gles(None))
             .build(Some(raw_window_handle));
+        let fallback_context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
         unsafe {
             glutin_display
                 .create_context(config, &context_attributes)
@@ -111,7 +110,13 @@ impl App {
     }
 
     fn ensure_surface_and_context<T>(&mut self, event_loop: &EventLoopWindowTarget<T>) {
-        let window = winit::window::Window::new(event_loop).unwrap();
+        // the size is ignored on Android, on desktop it gives a phone-like portrait window
+        let window = winit::window::WindowBuilder::new()
+            .with_title("Panther")
+            .with_inner_size(winit::dpi::LogicalSize::new(405.0, 810.0))
+            .with_resizable(false)
+            .build(event_loop)
+            .unwrap();
         let raw_window_handle = window.raw_window_handle();
 
         // Lazily initialize, egl, wgl, glx etc
@@ -216,15 +221,8 @@ impl App {
         if let Some(ref surface_state) = self.surface_state {
             if let Some(ctx) = &self.context {
                 if self.app_state.renderer_ready() {
-                    match self.app_state.update() {
-                        ScreenManagementCmd::PopScreen => {
-                            self.app_state.pop_screen();
-                        }
-                        ScreenManagementCmd::PushScreen(screen) => {
-                            self.app_state.push_screen(screen);
-                        }
-                        _ => {}
-                    }
+                    let cmd = self.app_state.update();
+                    self.app_state.navigate(cmd);
                     self.app_state.draw();
 
 
@@ -244,15 +242,8 @@ impl App {
     /// can potentially call exit
     pub fn handle_back_button(&mut self) {
         if let Some(screen) = self.app_state.get_input_screen() {
-            match screen.back() {
-                ScreenManagementCmd::PopScreen => {
-                    self.app_state.pop_screen();
-                }
-                ScreenManagementCmd::PushScreen(screen) => {
-                    self.app_state.push_screen(screen);
-                }
-                _ => {}
-            }
+            let cmd = screen.back();
+            self.app_state.navigate(cmd);
         }
         else {
             log::warn!("Back button pressed, but no screen to send it to");
@@ -301,22 +292,9 @@ impl App {
                     }
                 }
                 winit::event::TouchPhase::Ended => {
-                    if let Some(touch_state) = self.touch_state.remove(&id) {
-                        match touch_state {
-                            TouchState::MovingStart(_, _, _) => {
-                                match screen.press((location.x / screen_width, y_ratio - location.y / screen_width)) {
-                                    ScreenManagementCmd::PushScreen(screen) => {
-                                        self.app_state.push_screen(screen);
-                                    }
-                                    ScreenManagementCmd::PopScreen => {
-                                        self.app_state.pop_screen();
-                                    }
-                                    _ => {}
-
-                                }
-                            }
-                            _ => {}
-                        }
+                    if let Some(TouchState::MovingStart(_, _, _)) = self.touch_state.remove(&id) {
+                        let cmd = screen.press((location.x / screen_width, y_ratio - location.y / screen_width));
+                        self.app_state.navigate(cmd);
                     }
                 }
                 winit::event::TouchPhase::Cancelled => {
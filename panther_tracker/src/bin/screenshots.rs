@@ -0,0 +1,30 @@
+// Renders every screen offscreen and writes the golden images checked by `tests/golden.rs`.
+//
+//   cargo run --target x86_64-unknown-linux-gnu --features headless --bin screenshots [-- --golden <dir>]
+
+use std::path::PathBuf;
+use panther::render::golden::{golden_dir, render_screens, DIMS};
+use panther::render::headless::HeadlessRenderer;
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Warn)
+        .parse_default_env()
+        .init();
+
+    let mut golden_dir = golden_dir();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--golden" => golden_dir = PathBuf::from(args.next().expect("--golden needs a directory")),
+            other => panic!("Unknown argument {}", other),
+        }
+    }
+
+    let mut renderer = HeadlessRenderer::new(DIMS).expect("Failed to create headless renderer");
+    std::fs::create_dir_all(&golden_dir).unwrap();
+    render_screens(&mut renderer, |name, image| {
+        image.save(golden_dir.join(format!("{}.png", name))).unwrap();
+        println!("{}: golden image updated", name);
+    });
+}
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+use log::{error, info};
+use crate::tracking::provider::simulated::SimulatedLocationProvider;
+
+/// Directory with records and tracks, `PANTHER_DATA_DIR` or `./data`
+pub fn data_dir() -> PathBuf {
+    std::env::var_os("PANTHER_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("data"))
+}
+
+/// Lap around a park at jogging pace, used when `PANTHER_ROUTE` is not set
+const DEMO_ROUTE: [(f64, f64); 5] = [
+    (59.97176, 30.32433),
+    (59.97360, 30.32920),
+    (59.97180, 30.33410),
+    (59.96990, 30.32900),
+    (59.97176, 30.32433),
+];
+
+/// There is no GPS on desktop, fixes are replayed from the GPX/TCX file in `PANTHER_ROUTE`
+/// (or the demo route) `PANTHER_ROUTE_SPEED` times faster than real time
+pub fn location_provider() -> SimulatedLocationProvider {
+    let speed_factor = std::env::var("PANTHER_ROUTE_SPEED").ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(1.0);
+
+    if let Some(path) = std::env::var_os("PANTHER_ROUTE").map(PathBuf::from) {
+        match SimulatedLocationProvider::from_file(&path, speed_factor) {
+            Ok(provider) => return provider,
+            Err(err) => error!("[Desktop] Failed to load route {}: {}", path.display(), err),
+        }
+    }
+
+    info!("[Desktop] Using demo route");
+    SimulatedLocationProvider::from_waypoints(&DEMO_ROUTE, 3.0, 1.0, 3.0, speed_factor)
+}
@@ -0,0 +1,161 @@
+// Human-readable values for the screens. Inputs are in meters, seconds and m/s,
+// outputs carry no units, so callers can draw units separately or append them.
+// Labels of the units are given by `UnitSystem`.
+
+use crate::settings::UnitSystem;
+
+pub const METERS_PER_KM: f64 = 1000.0;
+pub const METERS_PER_MILE: f64 = 1609.344;
+
+/// Paces slower than this are shown as "-": the runner is standing, not running
+const MAX_PACE: f64 = 3600.0;
+
+/// Text for missing values
+pub const NO_VALUE: &str = "-";
+
+/// "mm:ss" under an hour, "h:mm:ss" after. Truncated to whole seconds, like a stopwatch.
+pub fn duration(secs: f64) -> String {
+    let secs = if secs.is_finite() { secs.max(0.0) as u64 } else { 0 };
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+    else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// "m:ss" for seconds per distance unit, rounded to whole seconds
+pub fn pace(secs_per_unit: f64) -> String {
+    if !secs_per_unit.is_finite() || secs_per_unit <= 0.0 || secs_per_unit >= MAX_PACE {
+        return NO_VALUE.to_string();
+    }
+    let secs = secs_per_unit.round() as u64;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Seconds to cover `unit` meters at `speed`, None when not moving
+pub fn pace_from_speed(speed: f64, unit: f64) -> Option<f64> {
+    (speed.is_finite() && speed > 0.0).then(|| unit / speed)
+}
+
+/// Pace in min/km for a speed in m/s
+pub fn pace_per_km(speed: f64) -> String {
+    pace_from_speed(speed, METERS_PER_KM).map(pace).unwrap_or(NO_VALUE.to_string())
+}
+
+/// Pace in min/mi for a speed in m/s
+pub fn pace_per_mile(speed: f64) -> String {
+    pace_from_speed(speed, METERS_PER_MILE).map(pace).unwrap_or(NO_VALUE.to_string())
+}
+
+/// Pace in min/km or min/mi for a speed in m/s
+pub fn speed_pace(speed: f64, units: UnitSystem) -> String {
+    match units {
+        UnitSystem::Metric => pace_per_km(speed),
+        UnitSystem::Imperial => pace_per_mile(speed),
+    }
+}
+
+/// Pace of `distance` meters covered in `time` seconds
+pub fn pace_of(distance: f64, time: f64, units: UnitSystem) -> String {
+    if distance <= 0.0 {
+        return NO_VALUE.to_string();
+    }
+    pace(time / distance * units.long_unit())
+}
+
+/// km/h with one decimal for a speed in m/s
+pub fn speed_kmh(speed: f64) -> String {
+    speed_in(speed, METERS_PER_KM)
+}
+
+/// mph with one decimal for a speed in m/s
+pub fn speed_mph(speed: f64) -> String {
+    speed_in(speed, METERS_PER_MILE)
+}
+
+fn speed_in(speed: f64, unit: f64) -> String {
+    if !speed.is_finite() {
+        return NO_VALUE.to_string();
+    }
+    format!("{:.1}", speed.max(0.0) * 3600.0 / unit)
+}
+
+/// km/h or mph for a speed in m/s
+pub fn speed(speed: f64, units: UnitSystem) -> String {
+    speed_in(speed, units.long_unit())
+}
+
+/// Kilometers or miles with two decimals
+pub fn distance(meters: f64, units: UnitSystem) -> String {
+    format!("{:.2}", meters.max(0.0) / units.long_unit())
+}
+
+/// Whole meters or feet, for accuracy, elevation and laps.
+/// Negative values keep their sign, elevation goes down as well.
+pub fn short_distance(meters: f64, units: UnitSystem) -> String {
+    format!("{:.0}", meters / units.short_unit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_distance_and_speed_have_no_pace() {
+        assert_eq!(pace_of(0.0, 600.0, UnitSystem::Metric), NO_VALUE);
+        assert_eq!(pace_of(-5.0, 600.0, UnitSystem::Imperial), NO_VALUE);
+        assert_eq!(pace_from_speed(0.0, METERS_PER_KM), None);
+        assert_eq!(pace_from_speed(-1.0, METERS_PER_KM), None);
+        assert_eq!(pace_per_km(0.0), NO_VALUE);
+        assert_eq!(pace_per_mile(-2.5), NO_VALUE);
+        // standing still for an hour per kilometer
+        assert_eq!(pace_per_km(METERS_PER_KM / MAX_PACE), NO_VALUE);
+    }
+
+    #[test]
+    fn non_finite_speeds() {
+        for speed in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(pace_from_speed(speed, METERS_PER_KM), None);
+            assert_eq!(pace_per_km(speed), NO_VALUE);
+            assert_eq!(speed_kmh(speed), NO_VALUE);
+            assert_eq!(speed_mph(speed), NO_VALUE);
+        }
+        assert_eq!(pace(f64::NAN), NO_VALUE);
+        assert_eq!(duration(f64::NAN), "00:00");
+        assert_eq!(duration(f64::INFINITY), "00:00");
+    }
+
+    #[test]
+    fn pace_rounds_into_the_next_minute() {
+        assert_eq!(pace(299.4), "4:59");
+        assert_eq!(pace(299.5), "5:00");
+        assert_eq!(pace(59.5), "1:00");
+        // 3 m/s is 5:33.3 per km
+        assert_eq!(pace_per_km(3.0), "5:33");
+        assert_eq!(pace_of(5000.0, 1500.0, UnitSystem::Metric), "5:00");
+        assert_eq!(pace_of(METERS_PER_MILE, 480.0, UnitSystem::Imperial), "8:00");
+    }
+
+    #[test]
+    fn long_durations() {
+        assert_eq!(duration(0.0), "00:00");
+        assert_eq!(duration(-3.0), "00:00");
+        assert_eq!(duration(59.99), "00:59");
+        assert_eq!(duration(3599.9), "59:59");
+        assert_eq!(duration(3600.0), "1:00:00");
+        // ultramarathon
+        assert_eq!(duration(10.0 * 3600.0 + 62.0), "10:01:02");
+        // multi-day event, hours keep growing
+        assert_eq!(duration(123.0 * 3600.0 + 4.0 * 60.0 + 5.0), "123:04:05");
+    }
+
+    #[test]
+    fn speeds_and_distances() {
+        assert_eq!(speed_kmh(2.5), "9.0");
+        assert_eq!(speed_mph(METERS_PER_MILE / 3600.0 * 6.0), "6.0");
+        assert_eq!(speed_kmh(-1.0), "0.0");
+        assert_eq!(distance(42_195.0, UnitSystem::Metric), "42.20");
+        assert_eq!(distance(-10.0, UnitSystem::Metric), "0.00");
+    }
+}
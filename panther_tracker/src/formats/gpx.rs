@@ -0,0 +1,134 @@
+use std::fmt::Write;
+use crate::formats::{escape_xml, format_iso8601, parse_iso8601};
+use crate::formats::import::{ImportError, parse_number};
+use crate::render::screens::records::Record;
+use crate::track::{LocationMetric, Track};
+
+const GPX_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Panther tracker" xmlns="http://www.topografix.com/GPX/1/1" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://www.topografix.com/GPX/1/1 http://www.topografix.com/GPX/1/1/gpx.xsd">
+"#;
+
+/// GPX 1.1 document with one `<trk>`, every track segment (part between pauses) becomes a `<trkseg>`
+pub fn write_gpx(record: &Record, track: &Track) -> String {
+    let name = escape_xml(&format!("Training {}", record.id));
+    let desc = escape_xml(&format!("{:.2} m in {:.2} s at {:.2} m/s", record.distance, record.time, record.speed));
+
+    let mut out = String::from(GPX_HEADER);
+    // writing into String never fails
+    let _ = writeln!(out, "  <metadata>\n    <name>{}</name>\n    <time>{}</time>\n  </metadata>",
+                     name, format_iso8601(record.timestamp));
+    let _ = writeln!(out, "  <trk>\n    <name>{}</name>\n    <desc>{}</desc>\n    <type>running</type>", name, desc);
+
+    for segment in track.segments.iter().filter(|s| !s.is_empty()) {
+        out.push_str("    <trkseg>\n");
+        for point in segment {
+            let ele = point.altitude
+                .map(|altitude| format!("<ele>{:.2}</ele>", altitude))
+                .unwrap_or_default();
+            let _ = writeln!(out, "      <trkpt lat=\"{:.7}\" lon=\"{:.7}\">{}<time>{}</time></trkpt>",
+                             point.latitude, point.longitude, ele, format_iso8601(point.timestamp));
+        }
+        out.push_str("    </trkseg>\n");
+    }
+
+    out.push_str("  </trk>\n</gpx>\n");
+    out
+}
+
+/// Every `<trkseg>` of every `<trk>` becomes a segment, points must have `<time>`
+pub fn parse_gpx(doc: &roxmltree::Document) -> Result<Track, ImportError> {
+    let mut track = Track::new();
+
+    for segment in doc.descendants().filter(|n| n.has_tag_name("trkseg")) {
+        track.start_segment();
+        for point in segment.children().filter(|n| n.has_tag_name("trkpt")) {
+            let latitude = parse_number(point, "trkpt lat", point.attribute("lat"))?;
+            let longitude = parse_number(point, "trkpt lon", point.attribute("lon"))?;
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(ImportError::InvalidNumber("trkpt coordinates", format!("{}, {}", latitude, longitude)));
+            }
+
+            let time = point.children()
+                .find(|n| n.has_tag_name("time"))
+                .and_then(|n| n.text())
+                .ok_or(ImportError::MissingElement("trkpt time"))?;
+            let timestamp = parse_iso8601(time).ok_or_else(|| ImportError::InvalidTime(time.to_string()))?;
+
+            let altitude = match point.children().find(|n| n.has_tag_name("ele")) {
+                Some(ele) => Some(parse_number(ele, "trkpt ele", ele.text())?),
+                None => None,
+            };
+
+            track.push(LocationMetric {
+                latitude,
+                longitude,
+                accuracy: 0.0, // GPX has no horizontal accuracy
+                timestamp,
+                altitude,
+            });
+        }
+    }
+
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64, timestamp: f64, altitude: Option<f64>) -> LocationMetric {
+        LocationMetric { latitude, longitude, accuracy: 5.0, timestamp, altitude }
+    }
+
+    #[test]
+    fn roundtrip_keeps_segments_points_and_times() {
+        let mut track = Track::new();
+        track.push(point(59.9717001, 30.3243002, 946_684_800.0, Some(12.5)));
+        track.push(point(59.9718, 30.3245, 946_684_801.25, None));
+        // resumed after a pause
+        track.start_segment();
+        track.push(point(59.972, 30.325, 946_684_900.5, Some(-3.75)));
+        track.push(point(59.9721, 30.3251, 946_684_901.0, Some(-3.5)));
+
+        let record = Record {
+            id: 7,
+            timestamp: 946_684_901.0,
+            distance: 42.0,
+            time: 2.0,
+            elapsed_time: 101.0,
+            speed: 21.0,
+            track_points: track.len(),
+            splits: vec![],
+            laps: vec![],
+        };
+
+        let gpx = write_gpx(&record, &track);
+        assert!(gpx.contains("<time>2000-01-01T00:00:00.000Z</time>"));
+        assert!(gpx.contains("<time>2000-01-01T00:00:01.250Z</time>"));
+
+        let doc = roxmltree::Document::parse(&gpx).unwrap();
+        let parsed = parse_gpx(&doc).unwrap();
+        assert_eq!(parsed.segments.len(), 2);
+        assert_eq!(parsed.segments.iter().map(|s| s.len()).collect::<Vec<_>>(), [2, 2]);
+
+        for (written, read) in track.points().zip(parsed.points()) {
+            assert!((written.latitude - read.latitude).abs() < 1e-7);
+            assert!((written.longitude - read.longitude).abs() < 1e-7);
+            assert_eq!(written.timestamp, read.timestamp);
+            assert_eq!(written.altitude, read.altitude);
+        }
+    }
+
+    #[test]
+    fn empty_segments_are_skipped() {
+        let mut track = Track::new();
+        track.segments = vec![vec![], vec![point(1.0, 2.0, 100.0, None)], vec![]];
+
+        let record = Record {
+            id: 1, timestamp: 100.0, distance: 0.0, time: 0.0, elapsed_time: 0.0, speed: 0.0,
+            track_points: 1, splits: vec![], laps: vec![],
+        };
+        let gpx = write_gpx(&record, &track);
+        assert_eq!(gpx.matches("<trkseg>").count(), 1);
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use log::{error, info};
+use crate::formats::gpx::parse_gpx;
+use crate::formats::tcx::parse_tcx;
+use crate::render::screens::active_training::GpsData;
+use crate::render::screens::records::push_record;
+use crate::settings::settings;
+use crate::storage::Storage;
+use crate::track::Track;
+
+/// Files dropped here are imported on startup
+const IMPORT_DIR: &str = "import";
+const IMPORTED_DIR: &str = "done";
+const FAILED_DIR: &str = "failed";
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    /// Neither GPX nor TCX
+    UnknownFormat,
+    MissingElement(&'static str),
+    InvalidNumber(&'static str, String),
+    InvalidTime(String),
+    /// Parsed fine, but there is not a single usable point
+    EmptyTrack,
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "io error: {}", err),
+            ImportError::Xml(err) => write!(f, "xml error: {}", err),
+            ImportError::UnknownFormat => write!(f, "unknown file format"),
+            ImportError::MissingElement(what) => write!(f, "missing {}", what),
+            ImportError::InvalidNumber(what, value) => write!(f, "invalid {}: '{}'", what, value),
+            ImportError::InvalidTime(value) => write!(f, "invalid time: '{}'", value),
+            ImportError::EmptyTrack => write!(f, "no track points"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<std::io::Error> for ImportError {
+    fn from(value: std::io::Error) -> Self {
+        ImportError::Io(value)
+    }
+}
+
+impl From<roxmltree::Error> for ImportError {
+    fn from(value: roxmltree::Error) -> Self {
+        ImportError::Xml(value)
+    }
+}
+
+pub(crate) fn parse_number(node: roxmltree::Node, what: &'static str, value: Option<&str>) -> Result<f64, ImportError> {
+    let value = value.ok_or(ImportError::MissingElement(what))?.trim();
+    value.parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| ImportError::InvalidNumber(what, format!("{} (at {})", value, node.document().text_pos_at(node.range().start))))
+}
+
+/// Detects the format by the root element
+pub fn parse_track(text: &str) -> Result<Track, ImportError> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+
+    let track = match root.tag_name().name() {
+        "gpx" => parse_gpx(&doc)?,
+        "TrainingCenterDatabase" => parse_tcx(&doc)?,
+        _ => return Err(ImportError::UnknownFormat),
+    };
+
+    if track.is_empty() {
+        return Err(ImportError::EmptyTrack);
+    }
+    Ok(track)
+}
+
+/// Replays the track through `GpsData`, so imported totals are computed the same way as live ones
+pub fn replay_track(track: &Track) -> GpsData {
+    // splits follow the unit preference, like in a live training
+    let mut gps_data = GpsData::with_config(settings().tracking_config());
+    for segment in &track.segments {
+        gps_data.resume();
+        for point in segment {
+            gps_data.push_fix(point.clone());
+        }
+        gps_data.pause();
+    }
+    gps_data
+}
+
+/// Parses the file and stores it as a new record, returns the record id
+pub fn import_file(path: &Path) -> Result<u64, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    let track = parse_track(&text)?;
+
+    let finished_at = track.points().last().map(|p| p.timestamp).ok_or(ImportError::EmptyTrack)?;
+    let gps_data = replay_track(&track);
+    Ok(push_record(&gps_data, finished_at))
+}
+
+fn move_into(path: &Path, dir: &Path) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let target = dir.join(path.file_name().unwrap_or_default());
+    std::fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// Imports every `.gpx`/`.tcx` file from the import directory. Processed files are moved
+/// to `done`, broken ones to `failed`, so nothing is imported twice.
+pub fn import_pending(storage: &Storage) {
+    let import_dir = storage.data_dir().join(IMPORT_DIR);
+    let entries = match std::fs::read_dir(&import_dir) {
+        Ok(entries) => entries,
+        Err(_) => return, // nothing to import
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        if !matches!(extension.as_deref(), Some("gpx") | Some("tcx")) {
+            continue;
+        }
+
+        let target_dir = match import_file(&path) {
+            Ok(id) => {
+                info!("[Import] {} imported as record {}", path.display(), id);
+                import_dir.join(IMPORTED_DIR)
+            }
+            Err(err) => {
+                error!("[Import] Failed to import {}: {}", path.display(), err);
+                import_dir.join(FAILED_DIR)
+            }
+        };
+        if let Err(err) = move_into(&path, &target_dir) {
+            error!("[Import] Failed to move {}: {}", path.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gpx(points: &str) -> String {
+        format!("<gpx version=\"1.1\" xmlns=\"http://www.topografix.com/GPX/1/1\"><trk><trkseg>{}</trkseg></trk></gpx>", points)
+    }
+
+    const POINT: &str = "<trkpt lat=\"59.9\" lon=\"30.3\"><time>2024-04-10T12:30:05Z</time></trkpt>";
+
+    #[test]
+    fn format_is_detected_by_root() {
+        let track = parse_track(&gpx(POINT)).unwrap();
+        assert_eq!(track.len(), 1);
+
+        let tcx = "<TrainingCenterDatabase><Activities><Activity><Lap><Track><Trackpoint>\
+                   <Time>2024-04-10T12:30:05Z</Time>\
+                   <Position><LatitudeDegrees>59.9</LatitudeDegrees><LongitudeDegrees>30.3</LongitudeDegrees></Position>\
+                   </Trackpoint></Track></Lap></Activity></Activities></TrainingCenterDatabase>";
+        let track = parse_track(tcx).unwrap();
+        assert_eq!(track.len(), 1);
+        assert_eq!(track.last().unwrap().timestamp, 1_712_752_205.0);
+
+        assert!(matches!(parse_track("<kml><Document/></kml>"), Err(ImportError::UnknownFormat)));
+    }
+
+    #[test]
+    fn malformed_xml() {
+        assert!(matches!(parse_track("<gpx><trk>"), Err(ImportError::Xml(_))));
+        assert!(matches!(parse_track(""), Err(ImportError::Xml(_))));
+    }
+
+    #[test]
+    fn missing_time() {
+        let text = gpx("<trkpt lat=\"59.9\" lon=\"30.3\"></trkpt>");
+        assert!(matches!(parse_track(&text), Err(ImportError::MissingElement(_))));
+    }
+
+    #[test]
+    fn invalid_time() {
+        let text = gpx("<trkpt lat=\"59.9\" lon=\"30.3\"><time>10.04.2024 12:30</time></trkpt>");
+        assert!(matches!(parse_track(&text), Err(ImportError::InvalidTime(time)) if time == "10.04.2024 12:30"));
+    }
+
+    #[test]
+    fn invalid_number() {
+        let text = gpx("<trkpt lat=\"north\" lon=\"30.3\"><time>2024-04-10T12:30:05Z</time></trkpt>");
+        assert!(matches!(parse_track(&text), Err(ImportError::InvalidNumber(_, _))));
+        let text = gpx("<trkpt lat=\"NaN\" lon=\"30.3\"><time>2024-04-10T12:30:05Z</time></trkpt>");
+        assert!(matches!(parse_track(&text), Err(ImportError::InvalidNumber(_, _))));
+        let text = gpx("<trkpt lon=\"30.3\"><time>2024-04-10T12:30:05Z</time></trkpt>");
+        assert!(matches!(parse_track(&text), Err(ImportError::MissingElement(_))));
+    }
+
+    #[test]
+    fn empty_track() {
+        assert!(matches!(parse_track(&gpx("")), Err(ImportError::EmptyTrack)));
+        assert!(matches!(parse_track("<TrainingCenterDatabase/>"), Err(ImportError::EmptyTrack)));
+    }
+}
@@ -0,0 +1,163 @@
+pub mod gpx;
+pub mod tcx;
+pub mod import;
+
+/// Formats UNIX time as ISO-8601 UTC date, e.g. `2024-04-10T12:30:05.250Z`
+pub fn format_iso8601(unix_secs: f64) -> String {
+    let total_ms = (unix_secs * 1000.0).round() as i64;
+    let ms = total_ms.rem_euclid(1000);
+    let secs = total_ms.div_euclid(1000);
+
+    let days = secs.div_euclid(86_400);
+    let sec_of_day = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year, month, day,
+            sec_of_day / 3600, sec_of_day % 3600 / 60, sec_of_day % 60, ms)
+}
+
+/// Parses ISO-8601 date-time as used by GPX and TCX: `2024-04-10T12:30:05Z`,
+/// optional fraction of a second and `+03:00`, `+0300` or `+03` offsets are supported
+pub fn parse_iso8601(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let (date, time) = text.split_once(['T', ' '])?;
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(pos);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let offset = &offset[1..];
+        if !offset.is_ascii() {
+            return None;
+        }
+        let (h, m) = match offset.split_once(':') {
+            Some(parts) => parts,
+            None if offset.len() == 4 => offset.split_at(2),
+            None if offset.len() == 2 => (offset, "00"),
+            None => return None,
+        };
+        if h.len() != 2 || m.len() != 2 {
+            return None;
+        }
+        let h: i64 = h.parse().ok()?;
+        let m: i64 = m.parse().ok()?;
+        if h > 23 || m > 59 {
+            return None;
+        }
+        (time, sign * (h * 3600 + m * 60))
+    } else {
+        // no zone designator, treat as UTC
+        (time, 0)
+    };
+
+    let mut time_parts = time.splitn(3, ':');
+    let hours: i64 = time_parts.next()?.parse().ok()?;
+    let minutes: i64 = time_parts.next()?.parse().ok()?;
+    let seconds: f64 = time_parts.next()?.parse().ok()?;
+    if hours > 23 || minutes > 59 || !(0.0..61.0).contains(&seconds) {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    Some((days * 86_400 + hours * 3600 + minutes * 60 - offset_secs) as f64 + seconds)
+}
+
+/// (year, month, day) to days since 1970-01-01, inverse of `civil_from_days`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Days since 1970-01-01 to (year, month, day), see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&apos;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-04-10T12:30:05Z
+    const BASE: f64 = 1_712_752_205.0;
+
+    #[test]
+    fn format_then_parse() {
+        assert_eq!(format_iso8601(BASE + 0.25), "2024-04-10T12:30:05.250Z");
+        assert_eq!(format_iso8601(951_782_400.0), "2000-02-29T00:00:00.000Z");
+        assert_eq!(parse_iso8601("2024-04-10T12:30:05.250Z"), Some(BASE + 0.25));
+        assert_eq!(parse_iso8601(&format_iso8601(-86_400.0)), Some(-86_400.0));
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_iso8601("2024-04-10T12:30:05"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10 12:30:05z"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10T15:30:05+03:00"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10T15:30:05+0300"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10T15:30:05+03"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10T11:00:05-01:30"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10T11:00:05-0130"), Some(BASE));
+        assert_eq!(parse_iso8601("2024-04-10T15:30:05.5+0300"), Some(BASE + 0.5));
+    }
+
+    #[test]
+    fn malformed_offsets_are_rejected() {
+        for text in [
+            "2024-04-10T15:30:05+3:00",
+            "2024-04-10T15:30:05+030",
+            "2024-04-10T15:30:05+03000",
+            "2024-04-10T15:30:05+",
+            "2024-04-10T15:30:05+24:00",
+            "2024-04-10T15:30:05+03:60",
+            "2024-04-10T15:30:05+0é0",
+        ] {
+            assert_eq!(parse_iso8601(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn malformed_dates_are_rejected() {
+        for text in ["2024-04-10", "2024-13-10T12:30:05Z", "2024-04-10T24:30:05Z", "2024-04-10T12:60:05Z", "yesterday"] {
+            assert_eq!(parse_iso8601(text), None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::formats::import::{ImportError, parse_number};
+use crate::formats::parse_iso8601;
+use crate::track::{LocationMetric, Track};
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+/// Every `<Track>` of every lap becomes a segment. Trackpoints without a position
+/// (only heart rate, for example) are skipped.
+pub fn parse_tcx(doc: &roxmltree::Document) -> Result<Track, ImportError> {
+    let mut track = Track::new();
+
+    for tcx_track in doc.descendants().filter(|n| n.has_tag_name("Track")) {
+        track.start_segment();
+        for point in tcx_track.children().filter(|n| n.has_tag_name("Trackpoint")) {
+            let position = match child(point, "Position") {
+                Some(position) => position,
+                None => continue,
+            };
+
+            let latitude = parse_number(position, "LatitudeDegrees",
+                                        child(position, "LatitudeDegrees").and_then(|n| n.text()))?;
+            let longitude = parse_number(position, "LongitudeDegrees",
+                                         child(position, "LongitudeDegrees").and_then(|n| n.text()))?;
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(ImportError::InvalidNumber("Position coordinates", format!("{}, {}", latitude, longitude)));
+            }
+
+            let time = child(point, "Time")
+                .and_then(|n| n.text())
+                .ok_or(ImportError::MissingElement("Trackpoint Time"))?;
+            let timestamp = parse_iso8601(time).ok_or_else(|| ImportError::InvalidTime(time.to_string()))?;
+
+            let altitude = match child(point, "AltitudeMeters") {
+                Some(altitude) => Some(parse_number(altitude, "AltitudeMeters", altitude.text())?),
+                None => None,
+            };
+
+            track.push(LocationMetric {
+                latitude,
+                longitude,
+                accuracy: 0.0, // not present in TCX
+                timestamp,
+                altitude,
+            });
+        }
+    }
+
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Track, ImportError> {
+        parse_tcx(&roxmltree::Document::parse(text).unwrap())
+    }
+
+    fn trackpoint(lat: &str, time: &str) -> String {
+        format!("<Trackpoint><Time>{}</Time><Position><LatitudeDegrees>{}</LatitudeDegrees>\
+                 <LongitudeDegrees>30.3</LongitudeDegrees></Position><AltitudeMeters>12.5</AltitudeMeters></Trackpoint>", time, lat)
+    }
+
+    fn tcx(tracks: &[String]) -> String {
+        let tracks: String = tracks.iter().map(|t| format!("<Track>{}</Track>", t)).collect();
+        format!("<TrainingCenterDatabase xmlns=\"http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2\">\
+                 <Activities><Activity><Lap>{}</Lap></Activity></Activities></TrainingCenterDatabase>", tracks)
+    }
+
+    #[test]
+    fn tracks_become_segments() {
+        // heart rate only, no position
+        let heart_rate = "<Trackpoint><Time>2024-04-10T12:30:06Z</Time><HeartRateBpm><Value>120</Value></HeartRateBpm></Trackpoint>";
+        let text = tcx(&[
+            trackpoint("59.9", "2024-04-10T12:30:05Z") + heart_rate + &trackpoint("59.91", "2024-04-10T12:30:07Z"),
+            trackpoint("59.92", "2024-04-10T12:31:00Z"),
+        ]);
+
+        let track = parse(&text).unwrap();
+        assert_eq!(track.segments.iter().map(|s| s.len()).collect::<Vec<_>>(), [2, 1]);
+        let first = &track.segments[0][0];
+        assert_eq!((first.latitude, first.longitude), (59.9, 30.3));
+        assert_eq!(first.timestamp, 1_712_752_205.0);
+        assert_eq!(first.altitude, Some(12.5));
+        assert_eq!(track.segments[0][1].timestamp, 1_712_752_207.0);
+    }
+
+    #[test]
+    fn out_of_range_position_is_rejected() {
+        let text = tcx(&[trackpoint("90.5", "2024-04-10T12:30:05Z")]);
+        assert!(matches!(parse(&text), Err(ImportError::InvalidNumber("Position coordinates", _))));
+    }
+}
@@ -0,0 +1,261 @@
+// Geodesy helpers. All angles are in degrees, distances in meters.
+
+/// Mean Earth radius (IUGG), used by spherical formulas
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// WGS-84 ellipsoid
+pub const WGS84_A: f64 = 6_378_137.0;
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+pub const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+/// Great-circle distance on a sphere. Error is within 0.5%, fine for short steps between fixes.
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let phi1 = lat1.to_radians();
+    let phi2 = lat2.to_radians();
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+/// Distance on the WGS-84 ellipsoid (Vincenty's inverse formula), accurate to ~1mm.
+/// None if the iteration doesn't converge, which happens for nearly antipodal points.
+pub fn vincenty_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Option<f64> {
+    let l = (lon2 - lon1).to_radians();
+    let u1 = ((1.0 - WGS84_F) * lat1.to_radians().tan()).atan();
+    let u2 = ((1.0 - WGS84_F) * lat2.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..200 {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2)).sqrt();
+        if sin_sigma == 0.0 {
+            return Some(0.0); // coincident points
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        // on the equator cos2_alpha is 0
+        let cos_2sigma_m = if cos2_alpha != 0.0 {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+        } else {
+            0.0
+        };
+        let c = WGS84_F / 16.0 * cos2_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos2_alpha));
+
+        let prev_lambda = lambda;
+        lambda = l + (1.0 - c) * WGS84_F * sin_alpha
+            * (sigma + c * sin_sigma * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+        if (lambda - prev_lambda).abs() < 1e-12 {
+            let u2 = cos2_alpha * (WGS84_A * WGS84_A - WGS84_B * WGS84_B) / (WGS84_B * WGS84_B);
+            let a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+            let b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+            let delta_sigma = b * sin_sigma * (cos_2sigma_m + b / 4.0
+                * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)
+                - b / 6.0 * cos_2sigma_m * (-3.0 + 4.0 * sin_sigma * sin_sigma) * (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+            return Some(WGS84_B * a * (sigma - delta_sigma));
+        }
+    }
+    None
+}
+
+/// Initial bearing of the great circle from point 1 to point 2, 0..360 clockwise from north
+pub fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let phi1 = lat1.to_radians();
+    let phi2 = lat2.to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+
+    let y = d_lambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lambda.cos();
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+/// Point reached from (lat, lon) after `distance` meters along the great circle with the given initial bearing
+pub fn destination_point(lat: f64, lon: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let phi1 = lat.to_radians();
+    let lambda1 = lon.to_radians();
+    let theta = bearing.to_radians();
+    let delta = distance / EARTH_RADIUS;
+
+    let phi2 = (phi1.sin() * delta.cos() + phi1.cos() * delta.sin() * theta.cos()).asin();
+    let lambda2 = lambda1 + (theta.sin() * delta.sin() * phi1.cos()).atan2(delta.cos() - phi1.sin() * phi2.sin());
+
+    // normalise to -180..180
+    let lon2 = (lambda2.to_degrees() + 540.0) % 360.0 - 180.0;
+    (phi2.to_degrees(), lon2)
+}
+
+/// Earth-centered, earth-fixed coordinates of a point on the WGS-84 ellipsoid
+pub fn to_ecef(lat: f64, lon: f64, height: f64) -> (f64, f64, f64) {
+    let (sin_phi, cos_phi) = lat.to_radians().sin_cos();
+    let (sin_lambda, cos_lambda) = lon.to_radians().sin_cos();
+    let n = WGS84_A / (1.0 - WGS84_E2 * sin_phi * sin_phi).sqrt();
+
+    ((n + height) * cos_phi * cos_lambda,
+     (n + height) * cos_phi * sin_lambda,
+     (n * (1.0 - WGS84_E2) + height) * sin_phi)
+}
+
+/// Inverse of `to_ecef`, returns (lat, lon, height)
+pub fn from_ecef(x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    let lon = y.atan2(x);
+    let p = (x * x + y * y).sqrt();
+
+    // a few fixed point iterations are enough for sub-millimeter precision near the surface
+    let mut lat = z.atan2(p * (1.0 - WGS84_E2));
+    let mut height = 0.0;
+    for _ in 0..5 {
+        let sin_phi = lat.sin();
+        let n = WGS84_A / (1.0 - WGS84_E2 * sin_phi * sin_phi).sqrt();
+        height = p / lat.cos() - n;
+        lat = z.atan2(p * (1.0 - WGS84_E2 * n / (n + height)));
+    }
+    (lat.to_degrees(), lon.to_degrees(), height)
+}
+
+/// Local East-North-Up tangent plane around an origin point.
+/// Lets the track be handled in meters with no distortion near the origin.
+#[derive(Clone, Debug)]
+pub struct LocalFrame {
+    origin: (f64, f64),
+    origin_ecef: (f64, f64, f64),
+    sin_lat: f64,
+    cos_lat: f64,
+    sin_lon: f64,
+    cos_lon: f64,
+}
+
+impl LocalFrame {
+    pub fn new(lat: f64, lon: f64) -> Self {
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+        LocalFrame {
+            origin: (lat, lon),
+            origin_ecef: to_ecef(lat, lon, 0.0),
+            sin_lat,
+            cos_lat,
+            sin_lon,
+            cos_lon,
+        }
+    }
+
+    pub fn origin(&self) -> (f64, f64) {
+        self.origin
+    }
+
+    /// (east, north, up) in meters relative to the origin
+    pub fn project_enu(&self, lat: f64, lon: f64, height: f64) -> (f64, f64, f64) {
+        let (x, y, z) = to_ecef(lat, lon, height);
+        let (dx, dy, dz) = (x - self.origin_ecef.0, y - self.origin_ecef.1, z - self.origin_ecef.2);
+
+        let east = -self.sin_lon * dx + self.cos_lon * dy;
+        let north = -self.sin_lat * self.cos_lon * dx - self.sin_lat * self.sin_lon * dy + self.cos_lat * dz;
+        let up = self.cos_lat * self.cos_lon * dx + self.cos_lat * self.sin_lon * dy + self.sin_lat * dz;
+        (east, north, up)
+    }
+
+    /// (east, north) in meters of a point on the ellipsoid surface
+    pub fn project(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (east, north, _) = self.project_enu(lat, lon, 0.0);
+        (east, north)
+    }
+
+    /// Inverse of `project_enu`, returns (lat, lon, height)
+    pub fn unproject_enu(&self, east: f64, north: f64, up: f64) -> (f64, f64, f64) {
+        let dx = -self.sin_lon * east - self.sin_lat * self.cos_lon * north + self.cos_lat * self.cos_lon * up;
+        let dy = self.cos_lon * east - self.sin_lat * self.sin_lon * north + self.cos_lat * self.sin_lon * up;
+        let dz = self.cos_lat * north + self.sin_lat * up;
+        from_ecef(self.origin_ecef.0 + dx, self.origin_ecef.1 + dy, self.origin_ecef.2 + dz)
+    }
+
+    /// (lat, lon) of a point given in the tangent plane
+    pub fn unproject(&self, east: f64, north: f64) -> (f64, f64) {
+        // points of the plane lie slightly above the ellipsoid, the height is dropped
+        let (lat, lon, _) = self.unproject_enu(east, north, 0.0);
+        (lat, lon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dms(degrees: f64, minutes: f64, seconds: f64) -> f64 {
+        degrees.signum() * (degrees.abs() + minutes / 60.0 + seconds / 3600.0)
+    }
+
+    #[test]
+    fn vincenty_flinders_peak_to_buninyong() {
+        // the example of Vincenty's 1975 paper
+        let flinders_peak = (dms(-37.0, 57.0, 3.72030), dms(144.0, 25.0, 29.52440));
+        let buninyong = (dms(-37.0, 39.0, 10.15610), dms(143.0, 55.0, 35.38390));
+
+        let distance = vincenty_distance(flinders_peak.0, flinders_peak.1, buninyong.0, buninyong.1).unwrap();
+        assert!((distance - 54_972.271).abs() < 0.001, "{}", distance);
+
+        // the sphere is within 0.5%
+        let haversine = haversine_distance(flinders_peak.0, flinders_peak.1, buninyong.0, buninyong.1);
+        assert!((haversine - 54_972.271).abs() / 54_972.271 < 0.005, "{}", haversine);
+    }
+
+    #[test]
+    fn vincenty_meridian_degree() {
+        // the meridian arc integrated over the WGS-84 ellipsoid
+        let equator = vincenty_distance(0.0, 30.0, 1.0, 30.0).unwrap();
+        assert!((equator - 110_574.389).abs() < 0.01, "{}", equator);
+
+        let pole = vincenty_distance(89.0, 30.0, 90.0, 30.0).unwrap();
+        assert!((pole - 111_693.865).abs() < 0.01, "{}", pole);
+    }
+
+    #[test]
+    fn vincenty_coincident_and_antipodal() {
+        assert_eq!(vincenty_distance(59.97, 30.32, 59.97, 30.32), Some(0.0));
+        assert_eq!(vincenty_distance(0.0, 0.0, 0.5, 179.7), None);
+    }
+
+    #[test]
+    fn destination_point_roundtrip() {
+        let (lat1, lon1) = (59.9717, 30.3243);
+        for (lat2, lon2) in [(59.98, 30.34), (59.96, 30.30), (60.5, 29.0), (-33.86, 151.21)] {
+            let bearing = initial_bearing(lat1, lon1, lat2, lon2);
+            let distance = haversine_distance(lat1, lon1, lat2, lon2);
+            let (lat, lon) = destination_point(lat1, lon1, bearing, distance);
+            assert!((lat - lat2).abs() < 1e-9 && (lon - lon2).abs() < 1e-9, "{}, {}", lat, lon);
+        }
+    }
+
+    #[test]
+    fn destination_point_wraps_longitude() {
+        let (lat, lon) = destination_point(0.0, 179.9, 90.0, 30_000.0);
+        assert!(lat.abs() < 1e-9);
+        assert!((-180.0..-179.0).contains(&lon), "{}", lon);
+    }
+
+    #[test]
+    fn local_frame_roundtrip() {
+        let frame = LocalFrame::new(59.9717, 30.3243);
+        assert_eq!(frame.project(59.9717, 30.3243), (0.0, 0.0));
+
+        for (lat, lon) in [(59.98, 30.34), (59.9717, 30.3343), (59.95, 30.30)] {
+            let (east, north) = frame.project(lat, lon);
+            // planar distance matches the ellipsoid near the origin
+            let distance = vincenty_distance(59.9717, 30.3243, lat, lon).unwrap();
+            assert!(((east * east + north * north).sqrt() - distance).abs() < 0.5, "{}, {}", east, north);
+
+            let (lat2, lon2) = frame.unproject(east, north);
+            assert!((lat2 - lat).abs() < 1e-8 && (lon2 - lon).abs() < 1e-8, "{}, {}", lat2, lon2);
+        }
+
+        let (east, north, up) = frame.project_enu(59.98, 30.34, 120.0);
+        let (lat, lon, height) = frame.unproject_enu(east, north, up);
+        assert!((lat - 59.98).abs() < 1e-10 && (lon - 30.34).abs() < 1e-10 && (height - 120.0).abs() < 1e-4);
+    }
+}
@@ -1,22 +1,49 @@
+use std::path::PathBuf;
 use std::sync::{Arc};
 use std::sync::atomic::{AtomicBool, Ordering};
-use jni::{JavaVM, JNIEnv};
-use jni::objects::{JClass, JObject, JObjectArray, JValue};
-use jni::sys::{jdouble, jobject};
+#[cfg(target_os = "android")]
+use jni::JavaVM;
+#[cfg(target_os = "android")]
+use jni::objects::{JObject, JObjectArray, JValue};
+#[cfg(target_os = "android")]
+use jni::sys::jobject;
 use log::info;
+#[cfg(target_os = "android")]
 use parking_lot::Mutex;
 use raw_window_handle::HasRawDisplayHandle;
-use winit::event::{Event, WindowEvent};
-use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, Event, MouseButton, TouchPhase, VirtualKeyCode, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+#[cfg(any(target_os = "android", feature = "desktop"))]
+use winit::event_loop::EventLoopBuilder;
+#[cfg(target_os = "android")]
 use winit::platform::android::activity::AndroidApp;
 use crate::app::App;
+#[cfg(target_os = "android")]
+use crate::render::ANDROID_DATA_PATH;
+#[cfg(target_os = "android")]
+use crate::tracking::provider::android::AndroidLocationProvider;
+#[cfg(any(target_os = "android", feature = "desktop"))]
+use crate::tracking::provider::set_location_provider;
 
 pub mod app;
+#[cfg(feature = "desktop")]
+pub mod desktop;
+pub mod format;
+pub mod formats;
+pub mod geo;
 pub mod render;
+pub mod settings;
+pub mod storage;
+pub mod track;
+pub mod tracking;
 
+#[cfg(target_os = "android")]
 pub static JNI_ENV: Mutex<Option<usize>> = Mutex::new(None);
+#[cfg(target_os = "android")]
 pub static ACTIVITY_OBJ: Mutex<Option<JObject>> = Mutex::new(None);
 
+#[cfg(target_os = "android")]
 fn set_max_framerate(android_app: &AndroidApp) {
     let vm = unsafe { JavaVM::from_raw(android_app.vm_as_ptr() as _) }.unwrap();
     let mut env = vm.get_env().unwrap();
@@ -67,13 +94,19 @@ fn set_max_framerate(android_app: &AndroidApp) {
     ACTIVITY_OBJ.lock().replace(activity);
 }
 
-fn run(event_loop: EventLoop<()>) {
+// only the entry points run it, a host build without `desktop` is a library for tests and tools
+#[cfg_attr(not(any(target_os = "android", feature = "desktop")), allow(dead_code))]
+fn run(event_loop: EventLoop<()>, data_dir: PathBuf) {
     info!("Running mainloop...");
 
     let raw_display = event_loop.raw_display_handle();
 
     let exit_request = Arc::new(AtomicBool::new(false));
-    let mut app = Some(App::new(raw_display, exit_request.clone()));
+    let mut app = Some(App::new(raw_display, exit_request.clone(), data_dir));
+
+    // desktop: left mouse button acts as a single finger
+    let mut cursor_position = PhysicalPosition::new(0.0, 0.0);
+    let mut mouse_pressed = false;
 
 
 
@@ -111,6 +144,20 @@ fn run(event_loop: EventLoop<()>) {
                     app.handle_back_button();
                 }
 
+                Event::WindowEvent {
+                    event: winit::event::WindowEvent::KeyboardInput{
+                        input: winit::event::KeyboardInput {
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                        ..
+                    },
+                    ..
+                } => {
+                    app.handle_back_button();
+                }
+
                 Event::WindowEvent {
                     event: WindowEvent::Touch(winit::event::Touch {
                                                   phase,
@@ -123,6 +170,24 @@ fn run(event_loop: EventLoop<()>) {
                     app.handle_touch(id, location, phase);
                 }
 
+                Event::WindowEvent {
+                    event: WindowEvent::CursorMoved { position, .. },
+                    ..
+                } => {
+                    cursor_position = position;
+                    if mouse_pressed {
+                        app.handle_touch(0, cursor_position, TouchPhase::Moved);
+                    }
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::MouseInput { state, button: MouseButton::Left, .. },
+                    ..
+                } => {
+                    mouse_pressed = state == ElementState::Pressed;
+                    let phase = if mouse_pressed { TouchPhase::Started } else { TouchPhase::Ended };
+                    app.handle_touch(0, cursor_position, phase);
+                }
+
                 Event::RedrawRequested(_) => {
                     app.handle_redraw_request();
                 }
@@ -141,6 +206,7 @@ fn run(event_loop: EventLoop<()>) {
     });
 }
 
+#[cfg(target_os = "android")]
 #[no_mangle]
 fn android_main(app: AndroidApp) {
     use winit::platform::android::EventLoopBuilderExtAndroid;
@@ -150,7 +216,25 @@ fn android_main(app: AndroidApp) {
     );
 
     set_max_framerate(&app);
+    set_location_provider(Box::new(AndroidLocationProvider::new()));
+
+    let data_dir = app.internal_data_path()
+        .unwrap_or_else(|| PathBuf::from(ANDROID_DATA_PATH));
 
     let event_loop = EventLoopBuilder::new().with_android_app(app).build();
-    run(event_loop);
-}
\ No newline at end of file
+    run(event_loop, data_dir);
+}
+
+#[allow(dead_code)]
+#[cfg(feature = "desktop")]
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Info)
+        .parse_default_env()
+        .init();
+
+    set_location_provider(Box::new(desktop::location_provider()));
+
+    let event_loop = EventLoopBuilder::new().build();
+    run(event_loop, desktop::data_dir());
+}
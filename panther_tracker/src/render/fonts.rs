@@ -1,179 +1,356 @@
-use std::collections::BTreeMap;
-use std::sync::OnceLock;
-use ab_glyph::{Font, FontRef, Point, PxScaleFont, Rect, ScaleFont};
-use log::{error, debug, info};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use ab_glyph::{Font, FontRef, GlyphId, Point, PxScaleFont, Rect, ScaleFont};
+use log::{debug, info, warn};
 use crate::render::gl;
 use crate::render::gl::Gles2;
 use crate::render::gl::types::GLuint;
+use crate::render::utils::sdf::distance_field;
 
 static QUEENSIDES_FONT: &[u8] = include_bytes!("../../resources/fonts/queensides.ttf");
 static SPARKY_STONES_FONT: &[u8] = include_bytes!("../../resources/fonts/SparkyStones.ttf");
-
-#[derive(Clone)]
+/// Covers Cyrillic and symbols missing in the fonts above
+static FALLBACK_FONT: &[u8] = include_bytes!("../../resources/fonts/DejaVuSans.ttf");
+
+/// Drawn for characters none of the fonts has
+const REPLACEMENT_CHAR: char = '\u{FFFD}';
+
+/// Glyphs are stored as distance fields, which stay sharp when scaled up, so they are rasterized small
+const GLYPH_RASTER_SIZE: f32 = 96.0;
+/// Texels of the distance field around a glyph. Outlines and shadows fit into it.
+const SDF_SPREAD: u32 = 8;
+/// Distance fields are built from a finer raster, so thin strokes don't fall between texels
+const SDF_OVERSAMPLE: u32 = 2;
+/// Rasterized pixels in a unit of glyph metrics. Screens are laid out for glyphs
+/// of an 11 glyphs wide atlas, so the metrics stay in fractions of its width.
+const PX_PER_UNIT: f32 = GLYPH_RASTER_SIZE * 11.0;
+
+const PAGE_WIDTH: u32 = 1024;
+const PAGE_MIN_HEIGHT: u32 = 256;
+const PAGE_MAX_HEIGHT: u32 = 2048;
+/// Empty texels around a glyph, so linear filtering doesn't bleed the neighbours in
+const GLYPH_PADDING: u32 = 2;
+
+#[derive(Clone, Copy, Debug)]
 pub struct GlyphParams {
+    /// texels of the glyph distance field in its page
     pub texture_rect: Rect,
+    pub page: usize,
+    /// size of the glyph, zero for whitespace
+    pub width: f32,
+    pub height: f32,
     pub h_advance: f32,
     pub v_advance: f32,
     pub h_side_bearing: f32,
     pub v_side_bearing: f32,
 }
 
-#[derive(Clone)]
-pub struct FontData {
-    pub texture_id: GLuint,
-    pub glyph_params: BTreeMap<char, GlyphParams>,
-    pub ascent: f32,
-    pub descent: f32,
-    pub line_gap: f32,
-    font: PxScaleFont<FontRef<'static>>
+/// Texture glyphs are packed into, row by row. It grows down until `PAGE_MAX_HEIGHT`,
+/// texture coordinates are in texels, so growing doesn't move glyphs already packed.
+struct Page {
+    texture_id: GLuint,
+    height: u32,
+    /// copy of the texture, GLES can't read textures back when they grow
+    pixels: Vec<u8>,
+
+    row_x: u32,
+    row_y: u32,
+    row_height: u32,
 }
 
-impl FontData {
-    pub fn kern_space(&self, char1: char, char2: char) -> f32 {
-        self.font.kern(self.font.glyph_id(char1), self.font.glyph_id(char2))
+impl Page {
+    fn new(gl: &Gles2) -> Self {
+        let mut texture_id = 0;
+        unsafe {
+            gl.GenTextures(1, &mut texture_id);
+            gl.BindTexture(gl::TEXTURE_2D, texture_id);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        }
+        let mut page = Page {
+            texture_id,
+            height: 0,
+            pixels: vec![],
+
+            row_x: 0,
+            row_y: 0,
+            row_height: 0,
+        };
+        page.grow(gl, PAGE_MIN_HEIGHT);
+        page
     }
 
-    pub fn single_width(&self) -> f32 {
-        1.0 / GRID_SIZE as f32
+    fn grow(&mut self, gl: &Gles2, height: u32) {
+        debug!("[Fonts] Glyph page {} grows to {}x{}", self.texture_id, PAGE_WIDTH, height);
+        self.height = height;
+        self.pixels.resize((PAGE_WIDTH * height) as usize, 0);
+        unsafe {
+            gl.BindTexture(gl::TEXTURE_2D, self.texture_id);
+            gl.TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::R8 as i32,
+                PAGE_WIDTH as i32,
+                height as i32,
+                0,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                self.pixels.as_ptr() as *const _,
+            );
+        }
     }
-    pub fn single_height(&self) -> f32 {
-        1.0 / GRID_SIZE as f32
+
+    /// Top left corner of a free `width` x `height` area, None if the page is full
+    fn allocate(&mut self, gl: &Gles2, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width > PAGE_WIDTH {
+            return None;
+        }
+        if self.row_x + width > PAGE_WIDTH {
+            self.row_x = 0;
+            self.row_y += self.row_height;
+            self.row_height = 0;
+        }
+
+        let bottom = self.row_y + height.max(self.row_height);
+        if bottom > PAGE_MAX_HEIGHT {
+            return None;
+        }
+        if bottom > self.height {
+            let mut new_height = self.height;
+            while new_height < bottom {
+                new_height *= 2;
+            }
+            self.grow(gl, new_height.min(PAGE_MAX_HEIGHT));
+        }
+
+        let pos = (self.row_x, self.row_y);
+        self.row_x += width;
+        self.row_height = self.row_height.max(height);
+        Some(pos)
+    }
+
+    fn upload(&self, gl: &Gles2, x: u32, y: u32, width: u32, height: u32) {
+        let mut region = Vec::with_capacity((width * height) as usize);
+        for row in y..y + height {
+            let start = (row * PAGE_WIDTH + x) as usize;
+            region.extend_from_slice(&self.pixels[start..start + width as usize]);
+        }
+        unsafe {
+            gl.BindTexture(gl::TEXTURE_2D, self.texture_id);
+            gl.TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                width as i32,
+                height as i32,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                region.as_ptr() as *const _,
+            );
+        }
     }
 }
 
+/// Glyphs of a font rasterized so far
+#[derive(Default)]
+struct GlyphAtlas {
+    pages: Vec<Page>,
+    glyphs: HashMap<char, GlyphParams>,
+}
 
-pub struct FontLoader {
-    fonts: BTreeMap<String, FontData>
+impl GlyphAtlas {
+    fn allocate(&mut self, gl: &Gles2, width: u32, height: u32) -> (usize, u32, u32) {
+        if let Some((x, y)) = self.pages.last_mut().and_then(|page| page.allocate(gl, width, height)) {
+            return (self.pages.len() - 1, x, y);
+        }
+        let mut page = Page::new(gl);
+        let (x, y) = page.allocate(gl, width, height).expect("Glyph is larger than a glyph page");
+        self.pages.push(page);
+        (self.pages.len() - 1, x, y)
+    }
 }
 
-const GLYPH_CELL_SIZE: usize = 300;
-const GLYPH_RASTER_SIZE: f32 = 300.0;
-const GRID_SIZE: usize = 11;
+/// Font with its glyph atlas. Glyphs are rasterized the first time they are asked for,
+/// clones share the atlas.
+#[derive(Clone)]
+pub struct FontData {
+    atlas: Arc<Mutex<GlyphAtlas>>,
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+    /// distance between the baselines of two lines
+    pub line_height: f32,
+    font: PxScaleFont<FontRef<'static>>,
+    fallback: PxScaleFont<FontRef<'static>>,
+}
 
-pub fn load_font(gl: &Gles2, font: &'static [u8]) -> FontData {
-    let font = FontRef::try_from_slice(font).unwrap().into_scaled(GLYPH_RASTER_SIZE);
+impl FontData {
+    pub fn kern_space(&self, char1: char, char2: char) -> f32 {
+        let (id1, id2) = (self.font.glyph_id(char1), self.font.glyph_id(char2));
+        // glyphs of the fallback font aren't kerned
+        if id1.0 == 0 || id2.0 == 0 {
+            return 0.0;
+        }
+        self.font.kern(id1, id2) / PX_PER_UNIT
+    }
 
-    let mut glyph_params = BTreeMap::new();
+    /// Horizontal advance of `c`, without rasterizing it
+    pub fn advance(&self, c: char) -> f32 {
+        let (font, glyph_id) = self.find_glyph(c)
+            .or_else(|| self.find_glyph(REPLACEMENT_CHAR))
+            .unwrap_or((&self.font, self.font.glyph_id('?')));
+        font.h_advance(glyph_id) / PX_PER_UNIT
+    }
 
-    let ascent = font.ascent() / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
-    let descent = font.descent() / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
-    let line_gap = font.line_gap() / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
-    let height = font.height() / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
+    /// Parameters of the glyph of `c`, rasterizing it if it is new
+    pub fn glyph(&self, gl: &Gles2, c: char) -> GlyphParams {
+        let mut atlas = self.atlas.lock().unwrap();
+        self.glyph_in(gl, &mut atlas, c)
+    }
 
-    debug!("Font loaded! Ascent: {}, Descent: {}, Line gap: {}, Height: {}",
-          ascent, descent, line_gap, height);  // Load all characters into grid GRID_SIZE x GRID_SIZE
+    /// Distance field around every glyph, drawn quads are this much larger on each side
+    pub fn padding(&self) -> f32 {
+        SDF_SPREAD as f32 / PX_PER_UNIT
+    }
 
-    let mut i = 1;
-    let mut j = 1;
-    let mut buf = vec![0u8; GRID_SIZE * GRID_SIZE * GLYPH_CELL_SIZE * GLYPH_CELL_SIZE];
-    for c in ('A'..='Z').chain('a'..='z').chain('0'..='9').chain([',', '.', '!', '*', '\'', '?', ':', '-', '(', ')', '+', '/'].into_iter()) {
+    /// Texture of a glyph page
+    pub fn page_texture(&self, page: usize) -> GLuint {
+        self.atlas.lock().unwrap().pages[page].texture_id
+    }
 
-        let glyph_id = font.glyph_id(c);
-        let glyph = glyph_id
-            .with_scale_and_position(GLYPH_RASTER_SIZE,
-                 ab_glyph::point(i as f32 * GLYPH_CELL_SIZE as f32,
-                                 (j + 1) as f32 * GLYPH_CELL_SIZE as f32));
+    fn glyph_in(&self, gl: &Gles2, atlas: &mut GlyphAtlas, c: char) -> GlyphParams {
+        if let Some(params) = atlas.glyphs.get(&c) {
+            return *params;
+        }
 
-        let outline_glyph = font.outline_glyph(glyph).unwrap_or_else(|| {
+        let params = match self.find_glyph(c) {
+            Some((font, glyph_id)) => self.rasterize(gl, atlas, font, glyph_id),
+            None if c != REPLACEMENT_CHAR => {
+                warn!("[Fonts] No glyph for {:?}, drawing a replacement", c);
+                self.glyph_in(gl, atlas, REPLACEMENT_CHAR)
+            }
+            None => self.rasterize(gl, atlas, &self.font, self.font.glyph_id('?')),
+        };
 
-            let glyph_id = font.glyph_id('x');
-            let glyph = glyph_id
-                .with_scale_and_position(GLYPH_RASTER_SIZE,
-                                         ab_glyph::point(i as f32 * GLYPH_CELL_SIZE as f32,
-                                                         (j + 1) as f32 * GLYPH_CELL_SIZE as f32));
+        atlas.glyphs.insert(c, params);
+        params
+    }
 
-            font.outline_glyph(glyph).unwrap()
-        });
-        let px_bounds = outline_glyph.px_bounds();
-        debug!("{}: px_bounds: {:?}", c, px_bounds);
+    /// Font drawing `c` and its glyph, the fallback font is used for characters the font lacks
+    fn find_glyph(&self, c: char) -> Option<(&PxScaleFont<FontRef<'static>>, GlyphId)> {
+        [&self.font, &self.fallback].into_iter()
+            .map(|font| (font, font.glyph_id(c)))
+            .find(|(_, glyph_id)| glyph_id.0 != 0)
+    }
 
-        let v_advance = font.v_advance(glyph_id) / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32); // should be 0, silly one
-        let h_advance = font.h_advance(glyph_id) / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
+    fn rasterize(&self, gl: &Gles2, atlas: &mut GlyphAtlas, font: &PxScaleFont<FontRef<'static>>, glyph_id: GlyphId) -> GlyphParams {
+        let glyph = glyph_id.with_scale_and_position(GLYPH_RASTER_SIZE * SDF_OVERSAMPLE as f32, ab_glyph::point(0.0, 0.0));
+
+        let h_advance = font.h_advance(glyph_id) / PX_PER_UNIT;
+        let v_advance = font.v_advance(glyph_id) / PX_PER_UNIT; // should be 0, silly one
+
+        // whitespace, nothing to draw
+        let Some(outline_glyph) = font.outline_glyph(glyph) else {
+            return GlyphParams {
+                texture_rect: Rect::default(),
+                page: 0,
+                width: 0.0,
+                height: 0.0,
+                h_advance,
+                v_advance,
+                h_side_bearing: 0.0,
+                v_side_bearing: 0.0,
+            };
+        };
 
-        // let v_side_bearing = font.v_side_bearing(glyph_id) / (FONT_RASTER_SIZE as f32 * GRID_SIZE as f32);
-        let v_side_bearing = 1.0 / GRID_SIZE as f32 * (j+1) as f32 + (font.v_side_bearing(glyph_id) - px_bounds.max.y) / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
-        let h_side_bearing = -1.0 / GRID_SIZE as f32 * (i) as f32 + (font.h_side_bearing(glyph_id) + px_bounds.min.x) / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
+        // fine raster, rounded up to whole texels of the field
+        let px_bounds = outline_glyph.px_bounds();
+        let (width, height) = ((px_bounds.width() as u32).div_ceil(SDF_OVERSAMPLE), (px_bounds.height() as u32).div_ceil(SDF_OVERSAMPLE));
+        let fine_width = width * SDF_OVERSAMPLE;
+        let bottom = px_bounds.min.y + (height * SDF_OVERSAMPLE) as f32;
 
-        let frac_w = px_bounds.width() / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
-        let frac_h = px_bounds.height() / (GLYPH_CELL_SIZE as f32 * GRID_SIZE as f32);
+        let mut coverage = vec![0.0; (fine_width * height * SDF_OVERSAMPLE) as usize];
+        outline_glyph.draw(|gx, gy, v| {
+            coverage[(gy * fine_width + gx) as usize] = v;
+        });
+        let field = distance_field(&coverage, fine_width as usize, (height * SDF_OVERSAMPLE) as usize,
+                                   SDF_SPREAD as usize, SDF_OVERSAMPLE as usize);
+        let (field_width, field_height) = (width + SDF_SPREAD * 2, height + SDF_SPREAD * 2);
+
+        let (page, x, y) = atlas.allocate(gl, field_width + GLYPH_PADDING * 2, field_height + GLYPH_PADDING * 2);
+        let (x, y) = (x + GLYPH_PADDING, y + GLYPH_PADDING);
+        {
+            let page = &mut atlas.pages[page];
+            for (row, values) in field.chunks(field_width as usize).enumerate() {
+                let start = ((y + row as u32) * PAGE_WIDTH + x) as usize;
+                page.pixels[start..start + field_width as usize].copy_from_slice(values);
+            }
+            page.upload(gl, x, y, field_width, field_height);
+        }
 
-        // fraction 0..1 in the whole texture
         let texture_rect = Rect {
-            min: Point {
-                x: 1.0 / GRID_SIZE as f32 * i as f32,
-                y: 1.0 / GRID_SIZE as f32 * j as f32
-            },
-            max: Point {
-                x: 1.0 / GRID_SIZE as f32 * i as f32 + frac_w,
-                y: 1.0 / GRID_SIZE as f32 * j as f32 + frac_h
-            }
+            min: Point { x: x as f32, y: y as f32 },
+            max: Point { x: (x + field_width) as f32, y: (y + field_height) as f32 },
         };
 
-        outline_glyph.draw(|x, y, v| {
-            let x = x + i * GLYPH_CELL_SIZE as u32;
-            let y = y + j * GLYPH_CELL_SIZE as u32;
-            let idx = (y * GRID_SIZE as u32 * GLYPH_CELL_SIZE as u32 + x) as usize;
-            buf[idx] = (v * 255.0) as u8;
-        });
-
-
-        glyph_params.insert(c, GlyphParams {
+        GlyphParams {
             texture_rect,
-            v_advance,
+            page,
+            width: width as f32 / PX_PER_UNIT,
+            height: height as f32 / PX_PER_UNIT,
             h_advance,
-            v_side_bearing,
-            h_side_bearing
-        });
-
-        i += 1;
-        if i as usize == GRID_SIZE - 1 {
-            i = 0;
-            j += 1;
-        }
-        if j as usize >= GRID_SIZE - 1 {
-            error!("Font grid too small, font loaded partially");
-            break;
+            v_advance,
+            h_side_bearing: (font.h_side_bearing(glyph_id) + px_bounds.min.x / SDF_OVERSAMPLE as f32) / PX_PER_UNIT,
+            v_side_bearing: (font.v_side_bearing(glyph_id) - bottom / SDF_OVERSAMPLE as f32) / PX_PER_UNIT,
         }
     }
+}
 
-    let mut texture_id = 0;
 
-    unsafe {
-        gl.GenTextures(1, &mut texture_id);
-        gl.BindTexture(gl::TEXTURE_2D, texture_id);
-        gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-        gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-        gl.TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::R8 as i32,
-            GRID_SIZE as i32 * GLYPH_CELL_SIZE as i32,
-            GRID_SIZE as i32 * GLYPH_CELL_SIZE as i32,
-            0,
-            gl::RED,
-            gl::UNSIGNED_BYTE,
-            buf.as_ptr() as *const _,
-        );
-    }
+pub struct FontLoader {
+    fonts: BTreeMap<String, FontData>
+}
+
+pub fn load_font(font: &'static [u8]) -> FontData {
+    let font = FontRef::try_from_slice(font).unwrap().into_scaled(GLYPH_RASTER_SIZE);
+    let fallback = FontRef::try_from_slice(FALLBACK_FONT).unwrap().into_scaled(GLYPH_RASTER_SIZE);
+
+    let ascent = font.ascent() / PX_PER_UNIT;
+    let descent = font.descent() / PX_PER_UNIT;
+    let line_gap = font.line_gap() / PX_PER_UNIT;
+    let height = font.height() / PX_PER_UNIT;
+
+    // lines are 1.2 heights of 'h' with the gap apart, 'h' is measured as it is rasterized
+    let h_glyph = font.glyph_id('h').with_scale(GLYPH_RASTER_SIZE * SDF_OVERSAMPLE as f32);
+    let h_height = font.outline_glyph(h_glyph)
+        .map_or(0, |outline_glyph| (outline_glyph.px_bounds().height() as u32).div_ceil(SDF_OVERSAMPLE)) as f32 / PX_PER_UNIT;
+    let line_height = (h_height + line_gap) * 1.2;
+
+    debug!("Font loaded! Ascent: {}, Descent: {}, Line gap: {}, Height: {}",
+          ascent, descent, line_gap, height);
 
     FontData {
-        texture_id,
-        glyph_params,
+        atlas: Arc::default(),
         font,
+        fallback,
         ascent,
         descent,
-        line_gap
+        line_gap,
+        line_height,
     }
 }
 
 impl FontLoader {
-    pub fn new(gl: &Gles2) -> Self {
+    pub fn new() -> Self {
         let mut fonts = BTreeMap::new();
 
         fonts.insert("queensides".to_string(),
-                     load_font(gl, QUEENSIDES_FONT));
+                     load_font(QUEENSIDES_FONT));
         fonts.insert("sparky-stones".to_string(),
-                     load_font(gl, SPARKY_STONES_FONT));
+                     load_font(SPARKY_STONES_FONT));
 
 
         FontLoader {
@@ -188,11 +365,11 @@ impl FontLoader {
 
 static FONTS: OnceLock<FontLoader> = OnceLock::new();
 
-pub fn load_fonts(gl: &Gles2) {
+pub fn load_fonts(_gl: &Gles2) {
     info!("Loading fonts started...");
-    FONTS.get_or_init(|| FontLoader::new(gl));
+    FONTS.get_or_init(FontLoader::new);
     info!("Loading fonts finished!");
 }
 pub fn get_font(name: &str) -> Option<FontData> {
     FONTS.get().unwrap().get_font(name)
-}
\ No newline at end of file
+}
@@ -0,0 +1,105 @@
+// Screens of the golden screenshot checks. `tests/golden.rs` compares them with `golden/*.png`,
+// the `screenshots` bin writes them there.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use image::RgbaImage;
+use crate::formats::import::replay_track;
+use crate::geo;
+use crate::render::headless::HeadlessRenderer;
+use crate::render::screens::active_training::ActiveTrainingScreen;
+use crate::render::screens::main::MainScreen;
+use crate::render::screens::paused_screen::PausedScreen;
+use crate::render::screens::record_detail::RecordDetailScreen;
+use crate::render::screens::records::{push_record, RecordsScreen, RECORDS_LIST};
+use crate::render::screens::settings::SettingsScreen;
+use crate::render::screens::stats::StatsScreen;
+use crate::render::screens::ScreenTrait;
+use crate::settings::{set_settings, Settings, Theme, UnitSystem};
+use crate::track::{LocationMetric, Track};
+
+pub const DIMS: (u32, u32) = (360, 720);
+const FRAMES: u32 = 90;
+
+/// 2000-01-01T00:00:00Z, fixed so the output doesn't depend on the current date
+const BASE_TIMESTAMP: f64 = 946_684_800.0;
+
+pub fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")
+}
+
+/// Straight run to the north-east at a constant speed and a gentle climb, one fix per second
+fn sample_track(start_time: f64, duration: u32, speed: f64) -> Track {
+    let mut track = Track::new();
+    for i in 0..=duration {
+        let (latitude, longitude) = geo::destination_point(59.9717, 30.3243, 45.0, speed * i as f64);
+        track.push(LocationMetric {
+            latitude,
+            longitude,
+            accuracy: 3.0,
+            timestamp: start_time + i as f64,
+            altitude: Some(20.0 + 0.01 * speed * i as f64),
+        });
+    }
+    track
+}
+
+fn seed_records() {
+    for (i, (duration, speed)) in [(600, 2.8), (1200, 3.1), (300, 4.0)].into_iter().enumerate() {
+        let start_time = BASE_TIMESTAMP + 86_400.0 * i as f64;
+        let gps_data = replay_track(&sample_track(start_time, duration, speed));
+        push_record(&gps_data, start_time + duration as f64);
+    }
+}
+
+/// Renders every checked screen and passes it to `visit` with its golden image name.
+/// Seeds the records list and changes settings on the way, so it's run once per process
+pub fn render_screens(renderer: &mut HeadlessRenderer, mut visit: impl FnMut(&str, RgbaImage)) {
+    let gl = renderer.gl();
+    let exit_request = Arc::new(AtomicBool::new(false));
+
+    seed_records();
+
+    let mut render = |name: &str, screens: &mut [&mut dyn ScreenTrait]| {
+        let image = renderer.render(screens, FRAMES);
+        visit(name, image);
+    };
+
+    let mut main_screen = MainScreen::new(gl.clone(), exit_request.clone());
+    render("main", &mut [&mut main_screen]);
+
+    let mut records_screen = RecordsScreen::new(gl.clone());
+    render("records", &mut [&mut records_screen]);
+
+    let record = RECORDS_LIST.lock().unwrap().records()[1].clone();
+    let mut record_detail_screen = RecordDetailScreen::new(gl.clone(), record, 1);
+    render("record_detail", &mut [&mut record_detail_screen]);
+
+    let mut stats_screen = StatsScreen::new(gl.clone());
+    render("stats", &mut [&mut stats_screen]);
+
+    set_settings(Settings { units: UnitSystem::Imperial, ..Settings::default() });
+    render("stats_imperial", &mut [&mut stats_screen]);
+    set_settings(Settings::default());
+
+    let mut settings_screen = SettingsScreen::new(gl.clone());
+    render("settings", &mut [&mut settings_screen]);
+
+    set_settings(Settings { theme: Theme::Dark, ..Settings::default() });
+    let mut dark_records_screen = RecordsScreen::new(gl.clone());
+    render("records_dark", &mut [&mut dark_records_screen]);
+    set_settings(Settings::default());
+
+    let mut active_training_screen = ActiveTrainingScreen::new(gl.clone());
+    render("active_training", &mut [&mut active_training_screen]);
+
+    // tap on the "avg" tab ear
+    active_training_screen.press((0.75, 1.25));
+    render("active_training_avg", &mut [&mut active_training_screen]);
+    active_training_screen.press((0.3, 1.25));
+
+    // paused screen is an overlay, drawn over the training it paused
+    let mut paused_screen = PausedScreen::new(gl.clone());
+    render("paused", &mut [&mut active_training_screen, &mut paused_screen]);
+}
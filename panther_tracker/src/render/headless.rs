@@ -0,0 +1,273 @@
+use std::fmt::{Display as FmtDisplay, Formatter};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use glutin::api::egl::context::PossiblyCurrentContext;
+use glutin::api::egl::device::Device;
+use glutin::api::egl::display::Display;
+use glutin::api::egl::surface::Surface;
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+use glutin::display::GetDisplayExtensions;
+use glutin::prelude::*;
+use glutin::surface::{PbufferSurface, SurfaceAttributesBuilder};
+use image::RgbaImage;
+use log::{info, warn};
+use crate::render::{check_gl_errors, gl, load_gl, PRESENT_FRAMEBUFFER, SURFACE_HEIGHT, SURFACE_WIDTH};
+use crate::render::gl::types::GLuint;
+use crate::render::renderer::with_renderer;
+use crate::render::screens::ScreenTrait;
+use crate::render::utils::clock;
+
+/// Clock step between rendered frames
+pub const FRAME_STEP: Duration = Duration::from_micros(16_667);
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    Glutin(glutin::error::Error),
+    NoDevice,
+    NoConfig,
+    IncompleteFramebuffer(u32),
+}
+
+impl FmtDisplay for HeadlessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadlessError::Glutin(err) => write!(f, "glutin error: {}", err),
+            HeadlessError::NoDevice => write!(f, "no usable EGL device"),
+            HeadlessError::NoConfig => write!(f, "no suitable EGL config"),
+            HeadlessError::IncompleteFramebuffer(status) => write!(f, "framebuffer is not complete: {:#x}", status),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+impl From<glutin::error::Error> for HeadlessError {
+    fn from(value: glutin::error::Error) -> Self {
+        HeadlessError::Glutin(value)
+    }
+}
+
+/// GLES context without a window, screens are presented into an offscreen framebuffer.
+/// Works with Mesa's llvmpipe, so screens can be rendered on a machine without a GPU.
+///
+/// Fonts and images are loaded into the first GL context of the process,
+/// so create only one renderer per process and don't mix it with a window.
+pub struct HeadlessRenderer {
+    gl: Arc<gl::Gl>,
+    dims: (u32, u32),
+    fbo: GLuint,
+    renderbuffer: GLuint,
+
+    // dropped in this order
+    _surface: Option<Surface<PbufferSurface>>,
+    _context: PossiblyCurrentContext,
+    _display: Display,
+}
+
+impl HeadlessRenderer {
+    pub fn new(dims: (u32, u32)) -> Result<Self, HeadlessError> {
+        let display = Self::create_display()?;
+
+        let template = ConfigTemplateBuilder::new()
+            .with_alpha_size(8)
+            .with_surface_type(ConfigSurfaceTypes::PBUFFER)
+            .build();
+        let config = unsafe { display.find_configs(template)? }
+            .next()
+            .ok_or(HeadlessError::NoConfig)?;
+
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::Gles(Some(Version::new(3, 0))))
+            .build(None);
+        let context = unsafe { display.create_context(&config, &context_attributes)? };
+
+        // the framebuffer below is the render target anyway, pbuffer is only needed
+        // to make the context current where surfaceless contexts are not supported
+        let (context, surface) = if display.extensions().contains("EGL_KHR_surfaceless_context") {
+            (context.make_current_surfaceless()?, None)
+        }
+        else {
+            let attrs = SurfaceAttributesBuilder::<PbufferSurface>::new().build(
+                NonZeroU32::new(dims.0).unwrap(),
+                NonZeroU32::new(dims.1).unwrap(),
+            );
+            let surface = unsafe { display.create_pbuffer_surface(&config, &attrs)? };
+            (context.make_current(&surface)?, Some(surface))
+        };
+
+        let gl = load_gl(&display);
+
+        let mut fbo = 0;
+        let mut renderbuffer = 0;
+        unsafe {
+            gl.GenRenderbuffers(1, &mut renderbuffer);
+            gl.BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+            gl.RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, dims.0 as i32, dims.1 as i32);
+
+            gl.GenFramebuffers(1, &mut fbo);
+            gl.BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl.FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, renderbuffer);
+            let status = gl.CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(HeadlessError::IncompleteFramebuffer(status));
+            }
+
+            // there is no window surface to take the default viewport from
+            gl.Viewport(0, 0, dims.0 as i32, dims.1 as i32);
+        }
+
+        SURFACE_WIDTH.store(dims.0, Ordering::Relaxed);
+        SURFACE_HEIGHT.store(dims.1, Ordering::Relaxed);
+        PRESENT_FRAMEBUFFER.store(fbo, Ordering::Relaxed);
+        clock::freeze();
+
+        Ok(HeadlessRenderer {
+            gl,
+            dims,
+            fbo,
+            renderbuffer,
+            _surface: surface,
+            _context: context,
+            _display: display,
+        })
+    }
+
+    fn create_display() -> Result<Display, HeadlessError> {
+        for device in Device::query_devices()? {
+            match unsafe { Display::with_device(&device, None) } {
+                Ok(display) => {
+                    info!("[Headless] Using EGL device {}", device.name().unwrap_or("unknown"));
+                    return Ok(display);
+                }
+                Err(err) => {
+                    warn!("[Headless] EGL device {} is not usable: {}", device.name().unwrap_or("unknown"), err);
+                }
+            }
+        }
+        Err(HeadlessError::NoDevice)
+    }
+
+    pub fn gl(&self) -> Arc<gl::Gl> {
+        self.gl.clone()
+    }
+
+    pub fn dims(&self) -> (u32, u32) {
+        self.dims
+    }
+
+    /// Updates the top screen and draws the whole stack `frames` times, then reads the result back.
+    /// Screen management commands returned by `update` are ignored.
+    pub fn render(&mut self, screens: &mut [&mut dyn ScreenTrait], frames: u32) -> RgbaImage {
+        self.draw_frames(screens, frames);
+        self.read_pixels()
+    }
+
+    /// Same as `render` without reading the result back
+    pub fn draw_frames(&mut self, screens: &mut [&mut dyn ScreenTrait], frames: u32) {
+        for _ in 0..frames {
+            if let Some(top) = screens.last_mut() {
+                top.update();
+            }
+
+            unsafe {
+                self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+                self.gl.ClearColor(0.1, 0.1, 0.1, 1.0);
+                self.gl.Clear(gl::COLOR_BUFFER_BIT);
+            }
+            for screen in screens.iter_mut() {
+                screen.draw();
+            }
+            with_renderer(|renderer| renderer.flush());
+            check_gl_errors(&self.gl);
+
+            clock::advance(FRAME_STEP);
+        }
+    }
+
+    pub fn read_pixels(&self) -> RgbaImage {
+        let (width, height) = self.dims;
+        let mut data = vec![0u8; (width * height * 4) as usize];
+        unsafe {
+            self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            self.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            self.gl.ReadPixels(0, 0, width as i32, height as i32, gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut _);
+        }
+
+        // GL rows go bottom to top
+        let row = (width * 4) as usize;
+        let flipped = data.chunks_exact(row).rev().flatten().copied().collect();
+        RgbaImage::from_raw(width, height, flipped).unwrap()
+    }
+}
+
+impl Drop for HeadlessRenderer {
+    fn drop(&mut self) {
+        PRESENT_FRAMEBUFFER.store(0, Ordering::Relaxed);
+        unsafe {
+            self.gl.DeleteFramebuffers(1, &self.fbo);
+            self.gl.DeleteRenderbuffers(1, &self.renderbuffer);
+        }
+    }
+}
+
+/// GL functions called since the last `reset_gl_calls`, most called first
+pub fn gl_calls() -> Vec<(&'static str, u64)> {
+    let mut calls: Vec<_> = gl::CALL_NAMES.iter()
+        .zip(gl::CALL_COUNTS.iter())
+        .map(|(name, count)| (*name, count.load(Ordering::Relaxed)))
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    calls.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    calls
+}
+
+pub fn reset_gl_calls() {
+    for count in gl::CALL_COUNTS.iter() {
+        count.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Result of comparing a rendered frame with a golden image
+#[derive(Clone, Debug)]
+pub struct ImageDiff {
+    /// pixels with any channel off by more than the tolerance
+    pub mismatched_pixels: u64,
+    pub max_channel_diff: u8,
+    /// red where pixels differ, dimmed actual image elsewhere
+    pub diff_image: RgbaImage,
+}
+
+/// None if the sizes differ
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Option<ImageDiff> {
+    if actual.dimensions() != expected.dimensions() {
+        return None;
+    }
+
+    let mut diff_image = RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched_pixels = 0;
+    let mut max_channel_diff = 0;
+    for ((a, e), d) in actual.pixels().zip(expected.pixels()).zip(diff_image.pixels_mut()) {
+        let diff = a.0.iter().zip(e.0.iter())
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap_or(0);
+        max_channel_diff = max_channel_diff.max(diff);
+
+        if diff > tolerance {
+            mismatched_pixels += 1;
+            d.0 = [255, 0, 0, 255];
+        }
+        else {
+            d.0 = [a.0[0] / 3, a.0[1] / 3, a.0[2] / 3, 255];
+        }
+    }
+
+    Some(ImageDiff {
+        mismatched_pixels,
+        max_channel_diff,
+        diff_image,
+    })
+}
@@ -1,6 +1,5 @@
 use std::ffi::{c_void, CStr, CString};
-use std::fs::File;
-use std::io::Read;
+use std::path::PathBuf;
 use std::sync::{Arc};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use glutin::display::{Display, GlDisplay};
@@ -9,13 +8,29 @@ use winit::dpi::PhysicalPosition;
 use crate::render::fonts::load_fonts;
 use crate::render::gl::UNPACK_ALIGNMENT;
 use crate::render::images::load_images;
-use crate::render::screens::main::MainScreen;
 use crate::render::screens::{ScreenManagementCmd, ScreenTrait};
-use crate::render::screens::records::{Records, RECORDS_LIST};
+use crate::render::screens::main::MainScreen;
+use crate::render::screens::records::{RecordsScreen, RECORDS_LIST};
+use crate::render::screens::stats::StatsScreen;
+use crate::render::navigator::Navigator;
+use crate::render::renderer::{init_renderer, with_renderer};
+use crate::render::widgets::nav_bar::NavTab;
+use crate::formats::import::import_pending;
+use crate::settings::set_settings;
+use crate::storage::init_storage;
+use crate::tracking::provider::pump_location_events;
 
 pub mod utils;
 pub mod objects;
 pub mod screens;
+pub mod navigator;
+pub mod widgets;
+pub mod renderer;
+pub mod shader;
+#[cfg(feature = "headless")]
+pub mod headless;
+#[cfg(feature = "headless")]
+pub mod golden;
 mod images;
 mod fonts;
 
@@ -24,18 +39,17 @@ pub mod gl {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 
     pub use Gles2 as Gl;
-}
 
-unsafe fn create_shader(
-    gl: &gl::Gl,
-    shader: gl::types::GLenum,
-    source: &[u8],
-) -> gl::types::GLuint {
-    let shader = gl.CreateShader(shader);
-    let len = source.len() as gl::types::GLint;
-    gl.ShaderSource(shader, 1, [source.as_ptr().cast()].as_ptr(), &len);
-    gl.CompileShader(shader);
-    shader
+    /// Calls of every function of `CALL_NAMES`, counted only in the headless build
+    #[cfg(feature = "headless")]
+    pub static CALL_COUNTS: [std::sync::atomic::AtomicU64; CALL_NAMES.len()] =
+        [const { std::sync::atomic::AtomicU64::new(0) }; CALL_NAMES.len()];
+
+    #[cfg(feature = "headless")]
+    #[inline]
+    fn count_call(index: usize) {
+        CALL_COUNTS[index].fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
 }
 
 pub fn get_gl_string(gl: &gl::Gl, variant: gl::types::GLenum) -> Option<&'static CStr> {
@@ -56,6 +70,8 @@ pub fn check_gl_errors(gl: &gl::Gl) {
 }
 pub static SURFACE_WIDTH: AtomicU32 = AtomicU32::new(0);
 pub static SURFACE_HEIGHT: AtomicU32 = AtomicU32::new(0);
+/// Framebuffer screens are presented to, 0 is the window surface
+pub static PRESENT_FRAMEBUFFER: AtomicU32 = AtomicU32::new(0);
 pub fn get_surface_y_ratio() -> f64 {
     let width = SURFACE_WIDTH.load(Ordering::Relaxed);
     let height = SURFACE_HEIGHT.load(Ordering::Relaxed);
@@ -72,7 +88,7 @@ pub enum MyInputEvent {
 }
 
 pub struct AppState {
-    screens: Vec<Box<dyn ScreenTrait>>,
+    navigator: Option<Navigator>,
     exit_request: Arc<AtomicBool>,
     gl: Option<Arc<gl::Gl>>,
 }
@@ -96,20 +112,84 @@ extern "system" fn gl_debug_callback(
 }
 
 
+/// Loads GL functions of the current context, sets up the global state and the renderer, uploads fonts and images.
+/// Fonts and images are loaded once per process, so only the first context gets them.
+pub fn load_gl(gl_display: &impl GlDisplay) -> Arc<gl::Gl> {
+    let gl = Arc::new(gl::Gl::load_with(|symbol| {
+        let symbol = CString::new(symbol).unwrap();
+        gl_display.get_proc_address(symbol.as_c_str()).cast()
+    }));
+
+    unsafe {
+        gl.PixelStorei(UNPACK_ALIGNMENT, 1);
+
+        gl.Enable(gl::BLEND);
+        gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+        gl.Enable(gl::DEBUG_OUTPUT);
+        gl.DebugMessageCallback(Some(gl_debug_callback), std::ptr::null());
+    }
+
+    init_renderer(gl.clone());
+    load_images(&gl);
+    load_fonts(&gl);
+
+    if let Some(renderer) = get_gl_string(&gl, gl::RENDERER) {
+        info!("Running on {}", renderer.to_string_lossy());
+    }
+    if let Some(version) = get_gl_string(&gl, gl::VERSION) {
+        info!("OpenGL Version {}", version.to_string_lossy());
+    }
+
+    if let Some(shaders_version) = get_gl_string(&gl, gl::SHADING_LANGUAGE_VERSION) {
+        info!("Shaders version on {}", shaders_version.to_string_lossy());
+    }
+
+    gl
+}
+
+/// New screen of the tab, the factory of the navigator
+fn tab_screen(tab: NavTab, gl: Arc<gl::Gl>, exit_request: Arc<AtomicBool>) -> Box<dyn ScreenTrait> {
+    match tab {
+        NavTab::Home => Box::new(MainScreen::new(gl, exit_request)),
+        NavTab::Records => Box::new(RecordsScreen::new(gl)),
+        NavTab::Stats => Box::new(StatsScreen::new(gl)),
+    }
+}
+
 pub const ANDROID_DATA_PATH: &str = "/data/user/0/com.skygrel.panther/files";
 impl AppState {
-    pub fn new(exit_request: Arc<AtomicBool>) -> Self {
-        // let records_path = format!("{}/records.json", ANDROID_DATA_PATH);
-        //
-        // //read test content if file exists
-        // if let Ok(file) = File::open(&records_path) {
-        //     let records: Records = serde_json::from_reader(file).unwrap();
-        //     let mut records_list = RECORDS_LIST.lock().unwrap();
-        //     *records_list = records;
-        // }
+    pub fn new(exit_request: Arc<AtomicBool>, data_dir: PathBuf) -> Self {
+        match init_storage(&data_dir) {
+            Ok(storage) => {
+                info!("[AppState] Data directory: {}", storage.data_dir().display());
+                match storage.load_settings() {
+                    Ok(settings) => set_settings(settings),
+                    // defaults are used and the broken file is overwritten on the next change
+                    Err(err) => error!("[AppState] Failed to load settings: {}", err),
+                }
+                match storage.load_records() {
+                    Ok(records) => {
+                        *RECORDS_LIST.lock().unwrap() = records;
+                        import_pending(storage);
+                    }
+                    Err(err) if err.is_corrupted() => {
+                        error!("[AppState] Failed to load records: {}", err);
+                        if let Err(err) = storage.quarantine_records() {
+                            error!("[AppState] Failed to move broken records away: {}", err);
+                        }
+                    }
+                    // newer or temporarily unreadable file is kept, new records aren't saved over it
+                    Err(err) => error!("[AppState] Failed to load records, they won't be saved: {}", err),
+                }
+            }
+            Err(err) => {
+                error!("[AppState] Failed to open data directory {}: {}", data_dir.display(), err);
+            }
+        }
 
         AppState {
-            screens: Vec::new(),
+            navigator: None,
             exit_request,
             gl: None
         }
@@ -119,63 +199,32 @@ impl AppState {
     pub fn ensure_renderer(&mut self, gl_display: &Display, dims: (u32, u32)) {
         let gl = self.gl.get_or_insert_with(|| {
             info!("[AppState] Initializing GL...");
-
-            let gl = gl::Gl::load_with(|symbol| {
-                let symbol = CString::new(symbol).unwrap();
-                gl_display.get_proc_address(symbol.as_c_str()).cast()
-            });
-
-            unsafe {
-                gl.PixelStorei(UNPACK_ALIGNMENT, 1);
-
-                gl.Enable(gl::BLEND);
-                gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-
-                gl.Enable(gl::DEBUG_OUTPUT);
-                gl.DebugMessageCallback(Some(gl_debug_callback), std::ptr::null());
-            }
-
-            load_images(&gl);
-            load_fonts(&gl);
-
-            if let Some(renderer) = get_gl_string(&gl, gl::RENDERER) {
-                info!("Running on {}", renderer.to_string_lossy());
-            }
-            if let Some(version) = get_gl_string(&gl, gl::VERSION) {
-                info!("OpenGL Version {}", version.to_string_lossy());
-            }
-
-            if let Some(shaders_version) = get_gl_string(&gl, gl::SHADING_LANGUAGE_VERSION) {
-                info!("Shaders version on {}", shaders_version.to_string_lossy());
-            }
-
-            Arc::new(gl)
+            load_gl(gl_display)
         });
 
         SURFACE_WIDTH.store(dims.0, Ordering::Relaxed);
         SURFACE_HEIGHT.store(dims.1, Ordering::Relaxed);
 
         //nice place to create first screen
-        if self.screens.is_empty() {
-            self.screens.push(Box::new(MainScreen::new(gl.clone(), self.exit_request.clone())));
+        if self.navigator.is_none() {
+            let gl = gl.clone();
+            let exit_request = self.exit_request.clone();
+            self.navigator = Some(Navigator::new(NavTab::Home, Box::new(move |tab| tab_screen(tab, gl.clone(), exit_request.clone()))));
         }
     }
 
     // called repeatedly just before draw, to determine, should we draw
     pub fn renderer_ready(&self) -> bool {
-        !self.screens.is_empty()
+        self.navigator.as_ref().is_some_and(|navigator| !navigator.is_empty())
     }
 
     pub fn get_input_screen(&mut self) -> Option<&mut Box<dyn ScreenTrait>> {
-        if !self.screens.is_empty() {
-            let i = self.screens.len() - 1;
-            Some(&mut self.screens[i])
-        } else {
-            None
-        }
+        self.navigator.as_mut()?.top()
     }
 
     pub fn update(&mut self) -> ScreenManagementCmd {
+        pump_location_events();
+
         // call input screen's update method
         if let Some(screen) = self.get_input_screen() {
             screen.update()
@@ -186,40 +235,32 @@ impl AppState {
 
     // called repeatedly from outside
     pub fn draw(&mut self) {
+        #[cfg(feature = "desktop")]
+        with_renderer(|renderer| renderer.reload_shaders());
+
         unsafe {
             let gl = self.gl.as_ref().unwrap();
+            gl.BindFramebuffer(gl::FRAMEBUFFER, PRESENT_FRAMEBUFFER.load(Ordering::Relaxed));
             gl.ClearColor(0.1, 0.1, 0.1, 1.0);
             gl.Clear(gl::COLOR_BUFFER_BIT);
         }
 
-        let mut screens_len = self.screens.len();
-        let mut i = 0;
-        while i < screens_len {
-            self.screens[i].draw();
-            if self.screens[i].is_expanded() && i > 0 {
-                info!("[ScreenStack]Screen {} is expanded, dropping back screens...", i);
-                let preserve_screens = self.screens.split_off(i);
-                self.screens = preserve_screens;
-                screens_len = self.screens.len();
-                i = 1;
-            }
-            else {
-                i += 1;
-            }
+        if let Some(navigator) = self.navigator.as_mut() {
+            navigator.draw();
         }
+        // screens present their own quads, nothing is left for a frame normally
+        with_renderer(|renderer| renderer.flush());
         check_gl_errors(self.gl.as_ref().unwrap());
     }
 
-    pub fn pop_screen(&mut self) {
-        info!("[ScreenStack] Popping top screen");
-        self.screens.pop();
-        if self.screens.is_empty() {
+    /// Applies a command returned by a screen
+    pub fn navigate(&mut self, cmd: ScreenManagementCmd) {
+        let Some(navigator) = self.navigator.as_mut() else {
+            return;
+        };
+        navigator.apply(cmd);
+        if navigator.is_empty() {
             self.exit_request.store(true, Ordering::Relaxed);
         }
     }
-
-    pub fn push_screen(&mut self, screen: Box<dyn ScreenTrait>) {
-        info!("[ScreenStack] Pushing new screen");
-        self.screens.push(screen);
-    }
 }
\ No newline at end of file
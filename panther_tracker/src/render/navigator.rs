@@ -0,0 +1,279 @@
+// Stack of screens the app shows. A new screen grows over the old ones with a circle
+// animation, the ones under it stop being drawn once it covers the surface (`is_expanded`).
+// Screens of the bottom bar tabs are kept when switching away and reused when switching back,
+// so tapping through the tabs doesn't pile up screens and their GL objects.
+
+use log::info;
+use crate::render::screens::{ScreenManagementCmd, ScreenTrait};
+use crate::render::widgets::nav_bar::NavTab;
+
+pub type TabFactory = Box<dyn FnMut(NavTab) -> Box<dyn ScreenTrait>>;
+
+pub struct Navigator {
+    /// bottom screen first, the top one gets the input
+    stack: Vec<Box<dyn ScreenTrait>>,
+    /// tab of the bottom screen, None when it isn't a tab screen (a training)
+    tab: Option<NavTab>,
+
+    /// screens replaced by `stack[leaving_under]`, drawn under it until it covers them.
+    /// Tab screens among them go to `tabs` afterwards.
+    leaving: Vec<(Option<NavTab>, Box<dyn ScreenTrait>)>,
+    leaving_under: usize,
+
+    /// screens of tabs switched away from
+    tabs: Vec<(NavTab, Box<dyn ScreenTrait>)>,
+    new_tab: TabFactory,
+}
+
+impl Navigator {
+    pub fn new(root: NavTab, mut new_tab: TabFactory) -> Self {
+        Navigator {
+            stack: vec![new_tab(root)],
+            tab: Some(root),
+
+            leaving: vec![],
+            leaving_under: 0,
+
+            tabs: vec![],
+            new_tab,
+        }
+    }
+
+    pub fn top(&mut self) -> Option<&mut Box<dyn ScreenTrait>> {
+        self.stack.last_mut()
+    }
+
+    /// Screens on the stack, the ones being covered and the cached tabs are not counted
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// All screens kept alive: the stack, the leaving ones and the cached tabs
+    pub fn screen_count(&self) -> usize {
+        self.stack.len() + self.leaving.len() + self.tabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn apply(&mut self, cmd: ScreenManagementCmd) {
+        match cmd {
+            ScreenManagementCmd::None => {}
+            ScreenManagementCmd::PushScreen(screen) => {
+                info!("[Navigator] Pushing new screen");
+                self.stack.push(screen);
+            }
+            ScreenManagementCmd::PopScreen => {
+                info!("[Navigator] Popping top screen");
+                self.stack.pop();
+                if self.stack.is_empty() {
+                    self.tab = None;
+                }
+                self.resume_top();
+            }
+            ScreenManagementCmd::ReplaceScreen(screen) => {
+                info!("[Navigator] Replacing top screen");
+                if let Some(old) = self.stack.pop() {
+                    // the replaced screen is done, even if it showed a tab
+                    self.leave(None, old);
+                }
+                if self.stack.is_empty() {
+                    self.tab = None;
+                }
+                self.stack.push(screen);
+            }
+            ScreenManagementCmd::PopToRoot => {
+                info!("[Navigator] Popping to the root screen");
+                self.stack.truncate(1);
+                self.resume_top();
+            }
+            ScreenManagementCmd::SwitchTab(tab) => self.switch_tab(tab),
+        }
+    }
+
+    fn switch_tab(&mut self, tab: NavTab) {
+        if self.tab == Some(tab) && self.stack.len() == 1 {
+            return;
+        }
+        info!("[Navigator] Switching to {:?} tab", tab);
+
+        let old_tab = self.tab.take();
+        for (i, screen) in std::mem::take(&mut self.stack).into_iter().enumerate() {
+            self.leave(old_tab.filter(|_| i == 0), screen);
+        }
+
+        let cached = self.tabs.iter().position(|(t, _)| *t == tab).map(|i| self.tabs.remove(i).1)
+            .or_else(|| {
+                let i = self.leaving.iter().position(|(t, _)| *t == Some(tab))?;
+                Some(self.leaving.remove(i).1)
+            });
+        let screen = match cached {
+            Some(mut screen) => {
+                screen.resume();
+                screen
+            }
+            None => (self.new_tab)(tab),
+        };
+        self.stack.push(screen);
+        self.tab = Some(tab);
+    }
+
+    fn leave(&mut self, tab: Option<NavTab>, screen: Box<dyn ScreenTrait>) {
+        if self.leaving_under != self.stack.len() {
+            self.finish_leaving();
+        }
+        self.leaving_under = self.stack.len();
+        self.leaving.push((tab, screen));
+    }
+
+    /// Drops the leaving screens, keeping the tab ones for later
+    fn finish_leaving(&mut self) {
+        for (tab, screen) in self.leaving.drain(..) {
+            if let Some(tab) = tab {
+                self.tabs.push((tab, screen));
+            }
+        }
+    }
+
+    fn resume_top(&mut self) {
+        if let Some(screen) = self.stack.last_mut() {
+            screen.resume();
+        }
+    }
+
+    pub fn draw(&mut self) {
+        let covering = self.stack.get(self.leaving_under).is_none_or(|screen| screen.is_expanded());
+        if !self.leaving.is_empty() && covering {
+            self.finish_leaving();
+        }
+
+        // everything under the topmost expanded screen is hidden by it
+        let first = self.stack.iter().rposition(|screen| screen.is_expanded()).unwrap_or(0);
+        let first = if self.leaving.is_empty() { first } else { first.min(self.leaving_under) };
+        for i in first..self.stack.len() {
+            if i == self.leaving_under {
+                for (_, screen) in self.leaving.iter_mut() {
+                    screen.draw();
+                }
+            }
+            self.stack[i].draw();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use super::*;
+
+    struct StubScreen;
+
+    impl ScreenTrait for StubScreen {
+        fn draw(&mut self) {}
+        fn is_expanded(&self) -> bool {
+            true
+        }
+    }
+
+    /// Navigator on the home tab and the number of tab screens its factory created
+    fn navigator() -> (Navigator, Rc<Cell<usize>>) {
+        let created = Rc::new(Cell::new(0));
+        let counter = created.clone();
+        let navigator = Navigator::new(NavTab::Home, Box::new(move |_| {
+            counter.set(counter.get() + 1);
+            Box::new(StubScreen)
+        }));
+        (navigator, created)
+    }
+
+    /// Applies the command and draws a frame, which finishes the transition
+    fn step(navigator: &mut Navigator, cmd: ScreenManagementCmd) {
+        navigator.apply(cmd);
+        navigator.draw();
+    }
+
+    #[test]
+    fn switching_tabs_reuses_their_screens() {
+        let (mut navigator, created) = navigator();
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 1));
+
+        step(&mut navigator, ScreenManagementCmd::SwitchTab(NavTab::Records));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 2));
+        step(&mut navigator, ScreenManagementCmd::SwitchTab(NavTab::Stats));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 3));
+
+        for _ in 0..10 {
+            for tab in [NavTab::Home, NavTab::Records, NavTab::Stats] {
+                step(&mut navigator, ScreenManagementCmd::SwitchTab(tab));
+                assert_eq!((navigator.depth(), navigator.screen_count()), (1, 3));
+            }
+        }
+        assert_eq!(created.get(), 3);
+    }
+
+    #[test]
+    fn switching_tabs_between_frames_stays_bounded() {
+        let (mut navigator, created) = navigator();
+        for _ in 0..10 {
+            for tab in [NavTab::Records, NavTab::Stats, NavTab::Home] {
+                navigator.apply(ScreenManagementCmd::SwitchTab(tab));
+                assert_eq!(navigator.depth(), 1);
+                assert!(navigator.screen_count() <= 3);
+            }
+        }
+        navigator.draw();
+        assert_eq!(navigator.screen_count(), 3);
+        assert_eq!(created.get(), 3);
+    }
+
+    #[test]
+    fn pop_to_root_drops_pushed_screens() {
+        let (mut navigator, _) = navigator();
+        step(&mut navigator, ScreenManagementCmd::PushScreen(Box::new(StubScreen)));
+        step(&mut navigator, ScreenManagementCmd::PushScreen(Box::new(StubScreen)));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (3, 3));
+
+        step(&mut navigator, ScreenManagementCmd::PopToRoot);
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 1));
+
+        // the pushed screens are not kept as the tab
+        step(&mut navigator, ScreenManagementCmd::PushScreen(Box::new(StubScreen)));
+        step(&mut navigator, ScreenManagementCmd::SwitchTab(NavTab::Records));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 2));
+    }
+
+    #[test]
+    fn replaced_home_is_created_again() {
+        let (mut navigator, created) = navigator();
+        // a training replaces the home screen
+        step(&mut navigator, ScreenManagementCmd::ReplaceScreen(Box::new(StubScreen)));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 1));
+
+        step(&mut navigator, ScreenManagementCmd::SwitchTab(NavTab::Home));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 1));
+        assert_eq!(created.get(), 2);
+
+        // home is a tab again, switching away keeps it
+        step(&mut navigator, ScreenManagementCmd::SwitchTab(NavTab::Stats));
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 2));
+    }
+
+    #[test]
+    fn popping_the_last_screen_empties_the_stack() {
+        let (mut navigator, _) = navigator();
+        step(&mut navigator, ScreenManagementCmd::PushScreen(Box::new(StubScreen)));
+        step(&mut navigator, ScreenManagementCmd::PopScreen);
+        assert_eq!((navigator.depth(), navigator.screen_count()), (1, 1));
+
+        step(&mut navigator, ScreenManagementCmd::PopScreen);
+        assert!(navigator.is_empty());
+        assert!(navigator.top().is_none());
+        assert_eq!((navigator.depth(), navigator.screen_count()), (0, 0));
+
+        // popping an empty stack does nothing
+        step(&mut navigator, ScreenManagementCmd::PopScreen);
+        assert_eq!(navigator.depth(), 0);
+    }
+}
@@ -4,12 +4,15 @@ use log::info;
 use crate::render::{get_surface_y_ratio, gl};
 use crate::render::gl::types::{GLint, GLuint};
 use crate::render::images::get_gif;
-use crate::render::objects::{BoxProgram};
+use crate::render::objects::{BoxQuad, BOX_VERTEX_SHADER_SOURCE};
+use crate::render::renderer::{with_renderer, Program};
+use crate::shader_source;
 use crate::render::utils::position::{FreePosition};
+use crate::render::utils::clock;
 
 pub struct StartAnimation {
-    gl: Arc<gl::Gl>,
-    box_prog: BoxProgram,
+    quad: BoxQuad,
+    program: Program,
 
     start: Instant,
     animation_start: Option<Instant>,
@@ -19,8 +22,6 @@ pub struct StartAnimation {
     img_textures: Vec<GLuint>,
     pub img_count: usize,
 
-    u_texture_loc: GLint,
-
     img_period: f64,
     last_frame_time: Instant,
     cur_frame: usize
@@ -28,35 +29,36 @@ pub struct StartAnimation {
 
 impl StartAnimation {
     pub fn new(gl: Arc<gl::Gl>, pos: FreePosition) -> Self {
-        unsafe {
-            let imgs = get_gif("running").unwrap();
+        let imgs = get_gif("running").unwrap();
 
-            let squad = BoxProgram::new(gl.clone(), pos.get(), include_bytes!("start-animation-frag.glsl"));
+        let program = with_renderer(|renderer| {
+            renderer.program("start_animation", BOX_VERTEX_SHADER_SOURCE, shader_source!("src/render/objects/start-animation-frag.glsl"))
+        });
 
-            let u_texture_loc = gl.GetUniformLocation(squad.program, b"tex\0".as_ptr() as *const _);
-            gl.Uniform1i(u_texture_loc, 1);
+        unsafe {
+            gl.UseProgram(program.id);
+            gl.Uniform1i(program.uniform_location(&gl, c"tex"), 0);
+        }
 
-            let img_textures: Vec<_> = imgs.into_iter().map(|i| i.texture_id).collect();
-            let img_count = img_textures.len();
+        let img_textures: Vec<_> = imgs.into_iter().map(|i| i.texture_id).collect();
+        let img_count = img_textures.len();
 
-            let t_loc = gl.GetUniformLocation(squad.program, b"t\0".as_ptr() as *const _);
+        let t_loc = program.uniform_location(&gl, c"t");
 
-            Self {
-                gl,
-                box_prog: squad,
-                animation_start: None,
-                start: Instant::now(),
+        Self {
+            quad: BoxQuad::new(pos.get()),
+            program,
+            animation_start: None,
+            start: clock::now(),
 
-                t_loc,
+            t_loc,
 
 
-                img_textures,
-                u_texture_loc,
-                img_count,
-                img_period: 0.03,
-                last_frame_time: Instant::now(),
-                cur_frame: 0,
-            }
+            img_textures,
+            img_count,
+            img_period: 0.03,
+            last_frame_time: clock::now(),
+            cur_frame: 0,
         }
     }
 
@@ -70,19 +72,19 @@ impl StartAnimation {
 
     pub fn launch(&mut self) {
         info!("Start pressed!");
-        self.animation_start = Some(Instant::now());
+        self.animation_start = Some(clock::now());
     }
 
     pub fn is_finished(&mut self) -> bool {
-        self.animation_start.map(|t|t.elapsed().as_secs_f32() > 8.0).unwrap_or(false)
+        self.animation_start.map(|t|clock::elapsed(t).as_secs_f32() > 8.0).unwrap_or(false)
     }
 
     pub fn draw(&mut self, texture_id: GLuint) {
         let anim_time = if let Some(start) = self.animation_start {
-            start.elapsed().as_secs_f32()
+            clock::elapsed(start).as_secs_f32()
         }
         else {
-            self.start.elapsed().as_secs_f32().sin() * 0.1 + 0.1
+            clock::elapsed(self.start).as_secs_f32().sin() * 0.1 + 0.1
         };
 
         let mut anim_times = [anim_time - 0.1, anim_time - 0.066, anim_time - 0.033, anim_time];
@@ -104,19 +106,18 @@ impl StartAnimation {
 
 
 
-        if self.last_frame_time.elapsed().as_secs_f64() > self.img_period {
-            self.last_frame_time = Instant::now();
+        if clock::elapsed(self.last_frame_time).as_secs_f64() > self.img_period {
+            self.last_frame_time = clock::now();
 
             let frame = self.cur_frame + 1;
             self.cur_frame = frame % self.img_count;
         }
 
-        self.box_prog.draw(texture_id, |gl| unsafe {
-            gl.UseProgram(self.box_prog.program);
+        with_renderer(|renderer| renderer.custom(texture_id, self.program, self.quad.bounds(), |gl| unsafe {
             gl.Uniform4f(self.t_loc, anim_times[3], anim_times[2], anim_times[1], anim_times[0]);
 
-            gl.ActiveTexture(gl::TEXTURE1);
+            gl.ActiveTexture(gl::TEXTURE0);
             gl.BindTexture(gl::TEXTURE_2D, self.img_textures[self.cur_frame]);
-        });
+        }));
     }
 }
\ No newline at end of file
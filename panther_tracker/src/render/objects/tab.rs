@@ -1,38 +1,35 @@
 use std::sync::{Arc};
 use crate::render::{get_surface_y_ratio, gl};
 use crate::render::gl::types::{GLint, GLuint};
-use crate::render::objects::{BoxProgram};
+use crate::render::objects::{BoxQuad, BOX_VERTEX_SHADER_SOURCE};
+use crate::render::renderer::{with_renderer, Program};
+use crate::shader_source;
 use crate::render::utils::position::FreePosition;
 
 pub struct Tab {
-    gl: Arc<gl::Gl>,
-    box_prog: BoxProgram,
+    quad: BoxQuad,
+    program: Program,
 
     u_color_loc: GLint,
-    color: (f32, f32, f32)
+    u_tab_offset_loc: GLint,
+    u_top_side_loc: GLint,
+    color: (f32, f32, f32),
+    tab_offset: f32,
 }
 
 impl Tab {
     pub fn new(gl: Arc<gl::Gl>, color: (f32, f32, f32), pos: FreePosition, tab_offset: f32) -> Self {
-        unsafe {
-            let squad = BoxProgram::new(gl.clone(), pos.get(), include_bytes!("tab-frag.glsl"));
+        let program = with_renderer(|renderer| renderer.program("tab", BOX_VERTEX_SHADER_SOURCE, shader_source!("src/render/objects/tab-frag.glsl")));
 
-            let u_color_loc = gl.GetUniformLocation(squad.program, b"color\0".as_ptr() as *const _);
-            gl.Uniform3f(u_color_loc, color.0, color.1, color.2);
+        Self {
+            quad: BoxQuad::new(pos.get()),
+            program,
 
-            let u_color_loc = gl.GetUniformLocation(squad.program, b"u_tab_offset\0".as_ptr() as *const _);
-            gl.Uniform1f(u_color_loc, tab_offset);
-
-            let u_top_side_loc = gl.GetUniformLocation(squad.program, b"u_top_side\0".as_ptr() as *const _);
-            gl.Uniform1f(u_top_side_loc, pos.get().1 as f32 + pos.get().3 as f32);
-
-            Self {
-                gl,
-                box_prog: squad,
-
-                u_color_loc,
-                color
-            }
+            u_color_loc: program.uniform_location(&gl, c"color"),
+            u_tab_offset_loc: program.uniform_location(&gl, c"u_tab_offset"),
+            u_top_side_loc: program.uniform_location(&gl, c"u_top_side"),
+            color,
+            tab_offset,
         }
     }
 
@@ -41,14 +38,15 @@ impl Tab {
     }
 
     pub fn set_color(&mut self, color: (f32, f32, f32)) {
-        let gl = &self.gl;
-        unsafe {
-            gl.UseProgram(self.box_prog.program);
-            gl.Uniform3f(self.u_color_loc, color.0, color.1, color.2);
-        }
+        self.color = color;
     }
 
     pub fn draw(&mut self, texture_id: GLuint) {
-        self.box_prog.draw(texture_id, |_| {});
+        let bounds = self.quad.bounds();
+        with_renderer(|renderer| renderer.custom(texture_id, self.program, bounds, |gl| unsafe {
+            gl.Uniform3f(self.u_color_loc, self.color.0, self.color.1, self.color.2);
+            gl.Uniform1f(self.u_tab_offset_loc, self.tab_offset);
+            gl.Uniform1f(self.u_top_side_loc, (bounds.1 + bounds.3) as f32);
+        }));
     }
-}
\ No newline at end of file
+}
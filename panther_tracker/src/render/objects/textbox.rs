@@ -1,61 +1,47 @@
-use std::mem;
+use std::ops::Range;
 use std::sync::{Arc};
-use ab_glyph::ScaleFont;
-use image::{GenericImageView};
-use log::{debug, info};
-use crate::render::{create_shader, get_surface_y_ratio, gl};
+use log::{debug};
+use crate::render::{gl};
 use crate::render::fonts::FontData;
 use crate::render::gl::{Gles2};
-use crate::render::gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
-
-const VERTEX_SHADER_SOURCE: &[u8] = include_bytes!("textbox-vert.glsl");
-const FRAGMENT_SHADER_SOURCE: &[u8] = include_bytes!("textbox-frag.glsl");
+use crate::render::gl::types::{GLuint};
+use crate::render::renderer::{with_renderer, Fill};
+use crate::render::utils::text_layout::{advance, layout, Line, TextLayout};
 
 pub struct TextBox {
-    program: GLuint,
-    vao: GLuint,
-    vbo: GLuint,
-    fbo: GLuint,
     gl: Arc<gl::Gl>,
     font_table: FontData,
 
     pos: (f32, f32),
     text: String,
     scale: f32,
+    layout: TextLayout,
+    lines: Vec<Line>,
     vert_buf: Vec<f32>,
+    /// (page texture, range of vert_buf)
+    draws: Vec<(GLuint, Range<usize>)>,
     style: u32,
 
     prev_y_offs: f32
 }
 
-fn build_vertex_buffer(gl: &Gles2, pos: &(f32, f32), scale: f32, vbo: GLuint, font_table: &FontData, text: String) -> Vec<f32> {
-    let mut temp_buf = vec![];
-
-    let mut prev_char = None;
-
-    let mut cursor_pos_x = pos.0;
-    let mut cursor_pos_y = pos.1;
-    for c in text.chars() {
-        match c {
-            '\n' => {
-                //move cursor to new line
-                cursor_pos_x = pos.0;
+/// Quads of the text, and the (page texture, range of the buffer) to draw them with
+fn build_vertex_buffer(gl: &Gles2, pos: &(f32, f32), scale: f32, font_table: &FontData, lines: &[Line]) -> (Vec<f32>, Vec<(GLuint, Range<usize>)>) {
+    let mut page_bufs: Vec<Vec<f32>> = vec![];
 
-                //use height value of 'n'
-                let glyph_params = font_table.glyph_params.get(&'h').unwrap();
+    for (i, line) in lines.iter().enumerate() {
+        let mut prev_char = None;
 
-                cursor_pos_y -= (glyph_params.texture_rect.height() + font_table.line_gap) * scale * 1.2;
-                prev_char = None;
-            }
-            ' ' => {
+        let mut cursor_pos_x = pos.0 + line.left;
+        let cursor_pos_y = pos.1 - font_table.line_height * scale * i as f32;
+        for c in line.text.chars() {
+            if c == ' ' {
                 //just move cursor
-                //use advance value of 'n'
-                let glyph_params = font_table.glyph_params.get(&'n').unwrap();
-                cursor_pos_x += glyph_params.h_advance * scale;
+                cursor_pos_x += advance(font_table, c) * scale;
                 prev_char = None;
             }
-            _ => {
-                let glyph_params = font_table.glyph_params.get(&c).unwrap();
+            else {
+                let glyph_params = font_table.glyph(gl, c);
 
                 debug!("Char: {}. h_advance: {}, h_side_bearing: {}, v_side_bearing: {}, v_advance: {}", c,
             glyph_params.h_advance, glyph_params.h_side_bearing, glyph_params.v_side_bearing, glyph_params.v_advance);
@@ -67,159 +53,124 @@ fn build_vertex_buffer(gl: &Gles2, pos: &(f32, f32), scale: f32, vbo: GLuint, fo
                 let w = raster_rect.width();
                 let h = raster_rect.height();
 
-                let cell_sz_x = w * scale;
-                let cell_sz_y = h * scale;
+                let padding = font_table.padding();
+                let cell_sz_x = (glyph_params.width + padding * 2.0) * scale;
+                let cell_sz_y = (glyph_params.height + padding * 2.0) * scale;
+
+                if let Some(p) = prev_char.replace(c) {
+                    cursor_pos_x += font_table.kern_space(p, c) * scale;
+                }
+
+                let glyph_x_fixed = cursor_pos_x + (glyph_params.h_side_bearing - padding) * scale;
+                let glyph_y_fixed = cursor_pos_y + (glyph_params.v_side_bearing - padding) * scale;
+
+                // whitespace other than a space
+                if glyph_params.width > 0.0 {
+                    if page_bufs.len() <= glyph_params.page {
+                        page_bufs.resize(glyph_params.page + 1, vec![]);
+                    }
+                    page_bufs[glyph_params.page].extend_from_slice(&[
+                        glyph_x_fixed + cell_sz_x, glyph_y_fixed, x + w, y + h,
+                        glyph_x_fixed + cell_sz_x, glyph_y_fixed + cell_sz_y, x + w, y,
+                        glyph_x_fixed, glyph_y_fixed + cell_sz_y, x, y,
+
+                        glyph_x_fixed + cell_sz_x, glyph_y_fixed, x + w, y + h,
+                        glyph_x_fixed, glyph_y_fixed + cell_sz_y, x, y,
+                        glyph_x_fixed, glyph_y_fixed, x, y + h,
+                    ]);
+                }
 
-                let prev_char = prev_char.replace(c);
-                let additional_kerning = if let Some(p) = prev_char {
-                    font_table.kern_space(p, c)
-                } else {
-                    0.0
-                };
-
-                let glyph_advance = (glyph_params.h_advance + additional_kerning) * scale;
-
-                let glyph_x_fixed = cursor_pos_x + glyph_params.h_side_bearing * scale;
-                let glyph_y_fixed = cursor_pos_y + glyph_params.v_side_bearing * scale;
-
-                temp_buf.extend_from_slice(&[
-                    glyph_x_fixed + cell_sz_x, glyph_y_fixed, x + w, y + h,
-                    glyph_x_fixed + cell_sz_x, glyph_y_fixed + cell_sz_y, x + w, y,
-                    glyph_x_fixed, glyph_y_fixed + cell_sz_y, x, y,
-
-                    glyph_x_fixed + cell_sz_x, glyph_y_fixed, x + w, y + h,
-                    glyph_x_fixed, glyph_y_fixed + cell_sz_y, x, y,
-                    glyph_x_fixed, glyph_y_fixed, x, y + h,
-                ]);
-
-                cursor_pos_x += glyph_advance;
+                cursor_pos_x += glyph_params.h_advance * scale;
                 debug!("new cursor x: {}", cursor_pos_x);
             }
         }
     }
 
-    unsafe {
-        gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
-        gl.BufferData(
-            gl::ARRAY_BUFFER,
-            (temp_buf.len() * mem::size_of::<f32>()) as GLsizeiptr,
-            temp_buf.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        );
+    // one range per glyph page
+    let mut temp_buf = vec![];
+    let mut draws = vec![];
+    for (page, buf) in page_bufs.into_iter().enumerate() {
+        if !buf.is_empty() {
+            draws.push((font_table.page_texture(page), temp_buf.len()..temp_buf.len() + buf.len()));
+            temp_buf.extend(buf);
+        }
     }
-    temp_buf
+    (temp_buf, draws)
 }
 
 impl TextBox {
+    /// style: 0 - rainbow, 1 - white, 2 - red, 3 - white with an outline, 4 - white with a shadow
     pub fn new(gl: Arc<gl::Gl>, font: FontData, text: String, pos: (f32, f32), scale: f32, style: u32) -> Self {
-        unsafe {
-            let vertex_shader = create_shader(&gl, gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE);
-            let fragment_shader = create_shader(&gl, gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE);
-
-            let program = gl.CreateProgram();
-
-            gl.AttachShader(program, vertex_shader);
-            gl.AttachShader(program, fragment_shader);
-
-            gl.LinkProgram(program);
-
-            gl.UseProgram(program);
-
-            gl.DeleteShader(vertex_shader);
-            gl.DeleteShader(fragment_shader);
-
-            let mut fbo = 0;
-            gl.GenFramebuffers(1, &mut fbo);
-
-            let mut vao = std::mem::zeroed();
-            gl.GenVertexArrays(1, &mut vao);
-            gl.BindVertexArray(vao);
-
-            let mut vbo = std::mem::zeroed();
-            gl.GenBuffers(1, &mut vbo);
-            let vert_buf = build_vertex_buffer(&gl, &pos, scale, vbo, &font, text.clone());
-
-
-            let ratio_location = gl.GetUniformLocation(program, b"y_ratio\0".as_ptr() as *const _);
-            let ratio = get_surface_y_ratio();
-            gl.Uniform1f(ratio_location, ratio as f32);
-
-            let pos_attrib = gl.GetAttribLocation(program, b"position\0".as_ptr() as *const _);
-            gl.VertexAttribPointer(
-                pos_attrib as GLuint,
-                2,
-                gl::FLOAT,
-                0,
-                4 * mem::size_of::<f32>() as GLsizei,
-                std::ptr::null(),
-            );
-            gl.EnableVertexAttribArray(pos_attrib as GLuint);
-
-            let texcoord_attrib = gl.GetAttribLocation(program, b"texcoord\0".as_ptr() as *const _);
-            gl.VertexAttribPointer(
-                texcoord_attrib as GLuint,
-                2,
-                gl::FLOAT,
-                0,
-                4 * mem::size_of::<f32>() as GLsizei,
-                (2 * mem::size_of::<f32>()) as *const _,
-            );
-            gl.EnableVertexAttribArray(texcoord_attrib as GLuint);
-
-            let tex_location = gl.GetUniformLocation(program, b"tex\0".as_ptr() as *const _);
-            gl.Uniform1i(tex_location, 1);
-
-            let style_location = gl.GetUniformLocation(program, b"u_style\0".as_ptr() as *const _);
-            gl.Uniform1i(style_location, style as GLint);
-
-            Self {
-                program,
-                vao,
-                vbo,
-                gl,
-                fbo,
-                font_table: font,
-                pos,
-                text,
+        let lines = layout(&font, &text, scale, &TextLayout::default());
+        let (vert_buf, draws) = build_vertex_buffer(&gl, &pos, scale, &font, &lines);
+
+        Self {
+            gl,
+            font_table: font,
+            pos,
+            text,
+
+            scale,
+            layout: TextLayout::default(),
+            lines,
+            vert_buf,
+            draws,
+            style,
+
+            prev_y_offs: 0.0
+        }
+    }
 
-                scale,
-                vert_buf,
-                style,
+    /// Alignment and limits of the lines, the text is left aligned and unlimited by default
+    pub fn layout(mut self, layout: TextLayout) -> Self {
+        self.layout = layout;
+        self.rebuild();
+        self
+    }
 
-                prev_y_offs: 0.0
-            }
-        }
+    fn rebuild(&mut self) {
+        self.lines = layout(&self.font_table, &self.text, self.scale, &self.layout);
+        let pos = (self.pos.0, self.pos.1 + self.prev_y_offs);
+        (self.vert_buf, self.draws) = build_vertex_buffer(&self.gl, &pos, self.scale, &self.font_table, &self.lines);
     }
 
+    /// The layout doesn't depend on the position, the quads are just moved
     pub fn set_pos(&mut self, pos: (f32, f32)) {
+        let movement = (pos.0 - self.pos.0, pos.1 - self.pos.1);
         self.pos = pos;
-        self.vert_buf = build_vertex_buffer(&self.gl, &self.pos, self.scale, self.vbo, &self.font_table, self.text.clone());
+
+        for vertex in self.vert_buf.chunks_exact_mut(4) {
+            vertex[0] += movement.0;
+            vertex[1] += movement.1;
+        }
     }
 
     pub fn set_pos_y_offs(&mut self, pos_y_offs: f64) {
         let movement = pos_y_offs as f32 - self.prev_y_offs;
         self.prev_y_offs = pos_y_offs as f32;
 
-        let mut vert_buf = self.vert_buf.clone();
-        let gl = &self.gl;
-
-        for i in (1..vert_buf.len()).step_by(4) {
-            let mut y = vert_buf[i];
-            y += movement;
-            vert_buf[i] = y;
+        for y in self.vert_buf.iter_mut().skip(1).step_by(4) {
+            *y += movement;
         }
+    }
 
-        unsafe {
-            gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl.BufferData(
-                gl::ARRAY_BUFFER,
-                (vert_buf.len() * mem::size_of::<f32>()) as GLsizeiptr,
-                vert_buf.as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-        }
+    /// Measured (width, height) of the lines, from the ascent of the first to the descent of the last
+    pub fn size(&self) -> (f64, f64) {
+        let width = self.lines.iter().map(|line| line.width).fold(0.0, f32::max);
+        let height = self.font_table.ascent - self.font_table.descent
+            + self.font_table.line_height * self.lines.len().saturating_sub(1) as f32;
+        (width as f64, (height * self.scale) as f64)
+    }
 
-        self.vert_buf = vert_buf;
+    /// Measured box of the text as placed, None for an empty text
+    pub fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        if self.lines.iter().all(|line| line.text.is_empty()) {
+            return None;
+        }
+        let left = self.lines.iter().map(|line| line.left).fold(f32::INFINITY, f32::min);
+        let top = self.pos.1 + self.prev_y_offs + self.font_table.ascent * self.scale;
+        let (width, height) = self.size();
+        Some(((self.pos.0 + left) as f64, top as f64 - height, width, height))
     }
 
     pub fn set_text(&mut self, text: String) {
@@ -227,41 +178,15 @@ impl TextBox {
             return;
         }
         self.text = text;
-        self.vert_buf = build_vertex_buffer(&self.gl, &self.pos, self.scale, self.vbo, &self.font_table, self.text.clone());
+        self.rebuild();
     }
 
     pub fn draw(&mut self, texture_id: GLuint) {
-        let gl = &self.gl;
-
-        unsafe {
-            gl.UseProgram(self.program);
-
-            gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
-            gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture_id, 0);
-
-            gl.BindVertexArray(self.vao);
-            gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-
-            gl.ActiveTexture(gl::TEXTURE1);
-            gl.BindTexture(gl::TEXTURE_2D, self.font_table.texture_id);
-
-            // let params = self.circ_anim.cur();
-            // gl.Uniform3f(self.circle, params.0, params.1, params.2);
-
-            gl.DrawArrays(gl::TRIANGLES, 0, self.vert_buf.len() as GLsizei / 4);
-        }
-    }
-}
-
-
-impl Drop for TextBox {
-    fn drop(&mut self) {
-        let gl = &self.gl;
-        unsafe {
-            gl.DeleteProgram(self.program);
-            gl.DeleteVertexArrays(1, &self.vao);
-            gl.DeleteBuffers(1, &self.vbo);
-            gl.DeleteFramebuffers(1, &self.fbo);
-        }
+        let style = self.style;
+        with_renderer(|renderer| {
+            for (page_texture, range) in self.draws.iter() {
+                renderer.triangles(texture_id, Fill::Text(*page_texture, style), (1.0, 1.0, 1.0, 1.0), &self.vert_buf[range.clone()]);
+            }
+        });
     }
 }
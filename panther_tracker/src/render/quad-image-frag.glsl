@@ -0,0 +1,41 @@
+#version 300 es
+precision highp float;
+
+// MAX_IMAGE_TEXTURES in renderer.rs
+uniform sampler2D textures[8];
+
+in vec2 v_texcoord;
+in vec4 v_color;
+flat in int v_fill;
+flat in int v_slot;
+
+out vec4 fragColor;
+
+// Fill::Mask and Fill::Texture in renderer.rs
+const int MASK = 0;
+
+vec4 texel_at(vec2 texcoord) {
+    // sampler arrays are indexed by constants only
+    switch (v_slot) {
+        case 0: return texture(textures[0], texcoord);
+        case 1: return texture(textures[1], texcoord);
+        case 2: return texture(textures[2], texcoord);
+        case 3: return texture(textures[3], texcoord);
+        case 4: return texture(textures[4], texcoord);
+        case 5: return texture(textures[5], texcoord);
+        case 6: return texture(textures[6], texcoord);
+        default: return texture(textures[7], texcoord);
+    }
+}
+
+void main() {
+    // images are stored top row first
+    vec4 texel = texel_at(vec2(v_texcoord.x, 1.0 - v_texcoord.y));
+
+    if (v_fill == MASK) {
+        fragColor = vec4(v_color.rgb, v_color.a * texel.a);
+    }
+    else {
+        fragColor = texel * v_color;
+    }
+}
@@ -0,0 +1,67 @@
+#version 300 es
+precision highp float;
+
+uniform sampler2D tex;
+
+in vec2 v_position;
+in vec2 v_texcoord;
+flat in int v_fill; // style
+
+out vec4 fragColor;
+
+// distance field value of the glyph edge, and of one texel (SDF_SPREAD in fonts.rs)
+const float EDGE = 0.5;
+const float TEXEL = 1.0 / 16.0;
+
+float distance_at(vec2 texcoord) {
+    // texcoords are in texels, glyph pages grow
+    return texture(tex, texcoord / vec2(textureSize(tex, 0))).r;
+}
+
+float coverage(float dist, float edge) {
+    // texels under the pixel, derivatives of the distance vanish in the middle of thin strokes
+    float smoothing = max(length(fwidth(v_texcoord)) * TEXEL * 0.5, 0.001);
+    // edge is moved out by the smoothing, so hairlines of minified text don't fall between pixels
+    return smoothstep(edge - smoothing * 2.0, edge, dist);
+}
+
+void main() {
+
+    float dist = distance_at(v_texcoord);
+    float intencity = coverage(dist, EDGE);
+
+    vec3 color = vec3(1.0, 0.9, 1.0);
+    float alpha = intencity;
+
+    //cute rainbow based on position
+    if (v_fill == 0) {
+        color = vec3(0.5 + 0.5 * sin(v_position.x), 0.5 + 0.5 * sin(v_position.y - 0.7), 0.5 + 0.5 * sin(v_position.x + v_position.y));
+    }
+    // white color, default
+
+    // red color
+    if (v_fill == 2) {
+        color = vec3(1.0, 0.1, 0.2);
+    }
+
+    // white with a dark outline
+    if (v_fill == 3) {
+        float outline = coverage(dist, EDGE - 2.5 * TEXEL);
+        color = mix(vec3(0.1, 0.05, 0.15), color, intencity);
+        alpha = outline;
+    }
+
+    // white with a soft shadow to the bottom right
+    if (v_fill == 4) {
+        float shadow = smoothstep(EDGE - 3.0 * TEXEL, EDGE + TEXEL, distance_at(v_texcoord - vec2(2.0, 2.0))) * 0.6;
+        color = mix(vec3(0.0, 0.0, 0.0), color, intencity);
+        alpha = intencity + shadow * (1.0 - intencity);
+    }
+
+    if (alpha > 0.01) {
+        fragColor = vec4(color, alpha);
+    }
+    else {
+        discard;
+    }
+}
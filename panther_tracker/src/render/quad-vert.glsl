@@ -3,17 +3,23 @@ precision highp float;
 
 in vec2 position;
 in vec2 texcoord;
+in vec4 color;
+in vec2 fill; // image kind or text style, texture slot
 
 uniform float y_ratio;
 
 out vec2 v_position;
 out vec2 v_texcoord;
+out vec4 v_color;
+flat out int v_fill;
+flat out int v_slot;
 
 void main() {
-
     v_position = position; // 0..1
 
     gl_Position = vec4(position.x * 2.0 - 1.0, position.y * 2.0 / y_ratio - 1.0, 0.0, 1.0);
-
     v_texcoord = texcoord;
-}
\ No newline at end of file
+    v_color = color;
+    v_fill = int(fill.x + 0.5);
+    v_slot = int(fill.y + 0.5);
+}
@@ -0,0 +1,415 @@
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::mem;
+use std::sync::Arc;
+use log::error;
+use crate::render::{get_surface_y_ratio, gl};
+use crate::render::gl::types::{GLint, GLsizei, GLsizeiptr, GLuint};
+use crate::render::shader::{ShaderCache, ShaderSource};
+use crate::shader_source;
+
+const VERTEX_SHADER_SOURCE: ShaderSource = shader_source!("src/render/quad-vert.glsl");
+const COLOR_SHADER_SOURCE: ShaderSource = shader_source!("src/render/quad-color-frag.glsl");
+const IMAGE_SHADER_SOURCE: ShaderSource = shader_source!("src/render/quad-image-frag.glsl");
+const TEXT_SHADER_SOURCE: ShaderSource = shader_source!("src/render/quad-text-frag.glsl");
+const PIPELINE_KEYS: [&str; 3] = ["quad_color", "quad_image", "quad_text"];
+
+/// Textures of one image batch, size of `textures` in quad-image-frag.glsl
+const MAX_IMAGE_TEXTURES: usize = 8;
+
+/// position, texcoord, color, fill
+const VERTEX_SIZE: usize = 10;
+
+/// Attribute locations, the same in every program, so they all read the one vertex buffer
+const ATTRIBUTES: [&CStr; 4] = [c"position", c"texcoord", c"color", c"fill"];
+const ATTRIBUTE_SIZES: [GLint; 4] = [2, 2, 4, 2];
+
+/// How a quad is filled
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fill {
+    /// Solid color
+    Color,
+    /// Color with the alpha of the texture
+    Mask(GLuint),
+    /// Colors of the texture
+    Texture(GLuint),
+    /// Glyph page distance field, texcoords are in texels. Style as of `TextBox::new`
+    Text(GLuint, u32),
+}
+
+/// Programs of the batches, a shader for every kind of fill keeps them cheap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pipeline {
+    Color,
+    Image,
+    Text,
+}
+
+impl Pipeline {
+    fn max_textures(&self) -> usize {
+        match self {
+            Pipeline::Color => 0,
+            Pipeline::Image => MAX_IMAGE_TEXTURES,
+            Pipeline::Text => 1,
+        }
+    }
+}
+
+impl Fill {
+    fn pipeline(&self) -> Pipeline {
+        match self {
+            Fill::Color => Pipeline::Color,
+            Fill::Mask(_) | Fill::Texture(_) => Pipeline::Image,
+            Fill::Text(..) => Pipeline::Text,
+        }
+    }
+
+    fn texture(&self) -> Option<GLuint> {
+        match self {
+            Fill::Color => None,
+            Fill::Mask(texture) | Fill::Texture(texture) | Fill::Text(texture, _) => Some(*texture),
+        }
+    }
+
+    /// First of the `fill` attribute: image kind in quad-image-frag.glsl or text style
+    fn kind(&self) -> f32 {
+        match self {
+            Fill::Color | Fill::Mask(_) => 0.0,
+            Fill::Texture(_) => 1.0,
+            Fill::Text(_, style) => *style as f32,
+        }
+    }
+}
+
+/// Linked program with the vertex layout of the renderer
+#[derive(Clone, Copy, Debug)]
+pub struct Program {
+    pub id: GLuint,
+    y_ratio: GLint,
+}
+
+impl Program {
+    pub fn uniform_location(&self, gl: &gl::Gl, name: &CStr) -> GLint {
+        unsafe { gl.GetUniformLocation(self.id, name.as_ptr()) }
+    }
+}
+
+/// Quads drawn with one call
+struct Batch {
+    pipeline: Pipeline,
+    /// bound to the texture units in order
+    textures: Vec<GLuint>,
+    vertices: Vec<f32>,
+    /// (left, bottom, right, top) of all the vertices
+    bounds: (f32, f32, f32, f32),
+}
+
+impl Batch {
+    /// Texture slot of the fill, if it can be drawn with the batch
+    fn slot(&self, pipeline: Pipeline, texture: Option<GLuint>) -> Option<usize> {
+        if pipeline != self.pipeline {
+            return None;
+        }
+        let Some(texture) = texture else {
+            return Some(0);
+        };
+        match self.textures.iter().position(|t| *t == texture) {
+            Some(slot) => Some(slot),
+            None => (self.textures.len() < pipeline.max_textures()).then_some(self.textures.len()),
+        }
+    }
+
+    fn overlaps(&self, bounds: (f32, f32, f32, f32)) -> bool {
+        self.bounds.0 < bounds.2 && bounds.0 < self.bounds.2 && self.bounds.1 < bounds.3 && bounds.1 < self.bounds.3
+    }
+}
+
+/// Draws into screen textures for all objects. Quads are collected until the target changes
+/// or the screen is presented, and drawn with as few calls as possible: a quad joins the latest
+/// batch of its program with a free texture slot, unless a batch after that one overlaps the quad,
+/// so the result is the same as drawing in order. Objects with their own programs draw right away.
+pub struct Renderer {
+    gl: Arc<gl::Gl>,
+    shaders: ShaderCache,
+    pipelines: [Program; 3],
+
+    vao: GLuint,
+    vbo: GLuint,
+    fbo: GLuint,
+    /// texture attached to the framebuffer
+    attached: GLuint,
+
+    target: GLuint,
+    batches: Vec<Batch>,
+    /// vertices of all the batches, uploaded at once
+    vertex_buf: Vec<f32>,
+}
+
+thread_local! {
+    static RENDERER: RefCell<Option<Renderer>> = const { RefCell::new(None) };
+}
+
+/// Sets up the renderer of the current GL context, called by `load_gl`
+pub fn init_renderer(gl: Arc<gl::Gl>) {
+    let renderer = Renderer::new(gl);
+    RENDERER.with(|cell| *cell.borrow_mut() = Some(renderer));
+}
+
+pub fn with_renderer<R>(f: impl FnOnce(&mut Renderer) -> R) -> R {
+    RENDERER.with(|cell| {
+        let mut renderer = cell.borrow_mut();
+        f(renderer.as_mut().expect("Renderer is set up by load_gl"))
+    })
+}
+
+impl Renderer {
+    fn new(gl: Arc<gl::Gl>) -> Self {
+        unsafe {
+            let mut vao = 0;
+            gl.GenVertexArrays(1, &mut vao);
+            gl.BindVertexArray(vao);
+
+            let mut vbo = 0;
+            gl.GenBuffers(1, &mut vbo);
+            gl.BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            let stride = (VERTEX_SIZE * mem::size_of::<f32>()) as GLsizei;
+            let mut offset = 0;
+            for (location, size) in ATTRIBUTE_SIZES.iter().enumerate() {
+                gl.VertexAttribPointer(location as GLuint, *size, gl::FLOAT, 0, stride, (offset * mem::size_of::<f32>()) as *const _);
+                gl.EnableVertexAttribArray(location as GLuint);
+                offset += *size as usize;
+            }
+
+            let mut fbo = 0;
+            gl.GenFramebuffers(1, &mut fbo);
+
+            let mut renderer = Renderer {
+                shaders: ShaderCache::new(gl.clone()),
+                gl,
+                pipelines: [Program { id: 0, y_ratio: -1 }; 3],
+                vao,
+                vbo,
+                fbo,
+                attached: 0,
+                target: 0,
+                batches: vec![],
+                vertex_buf: vec![],
+            };
+
+            renderer.setup_pipelines();
+            renderer
+        }
+    }
+
+    fn setup_pipelines(&mut self) {
+        self.pipelines = [
+            self.program(PIPELINE_KEYS[0], VERTEX_SHADER_SOURCE, COLOR_SHADER_SOURCE),
+            self.program(PIPELINE_KEYS[1], VERTEX_SHADER_SOURCE, IMAGE_SHADER_SOURCE),
+            self.program(PIPELINE_KEYS[2], VERTEX_SHADER_SOURCE, TEXT_SHADER_SOURCE),
+        ];
+
+        // texture slot i is on unit i
+        let image_program = self.pipelines[Pipeline::Image as usize];
+        let units: Vec<GLint> = (0..MAX_IMAGE_TEXTURES as GLint).collect();
+        let gl = &self.gl;
+        unsafe {
+            gl.UseProgram(image_program.id);
+            gl.Uniform1iv(image_program.uniform_location(gl, c"textures"), units.len() as GLsizei, units.as_ptr());
+        }
+    }
+
+    /// Program of the shaders, linked once per key. The shaders are part of the app,
+    /// so an error is a bug: it's logged with the info log and panics
+    pub fn program(&mut self, key: &'static str, vertex: ShaderSource, fragment: ShaderSource) -> Program {
+        let id = self.shaders.program(key, vertex, fragment, &ATTRIBUTES).unwrap_or_else(|err| {
+            error!("[Renderer] {}", err);
+            panic!("{}", err);
+        });
+        let y_ratio = unsafe { self.gl.GetUniformLocation(id, c"y_ratio".as_ptr()) };
+        Program { id, y_ratio }
+    }
+
+    /// Picks up shader files changed on disk, called every frame.
+    /// Uniforms set once by other programs' owners keep working only if they're unchanged
+    #[cfg(feature = "desktop")]
+    pub fn reload_shaders(&mut self) {
+        let relinked = self.shaders.reload();
+        if relinked.iter().any(|key| PIPELINE_KEYS.contains(key)) {
+            self.setup_pipelines();
+        }
+    }
+
+    /// Clears the target texture, it's drawn into until the next clear
+    pub fn clear(&mut self, target: GLuint) {
+        self.set_target(target);
+        // quads drawn before the clear are gone anyway
+        self.batches.clear();
+
+        unsafe {
+            self.bind_target();
+            let gl = &self.gl;
+            if gl.CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                panic!("Framebuffer is not complete");
+            }
+
+            gl.ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl.Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+
+    /// Quad of (left, bottom, width, height), texcoords go from 0 at the bottom left to 1 at the top right
+    pub fn quad(&mut self, target: GLuint, bounds: (f64, f64, f64, f64), fill: Fill, color: (f32, f32, f32, f32)) {
+        let (left, bottom, width, height) = bounds;
+        let (left, bottom, right, top) = (left as f32, bottom as f32, (left + width) as f32, (bottom + height) as f32);
+        self.triangles(target, fill, color, &[
+            left, bottom, 0.0, 0.0,
+            right, top, 1.0, 1.0,
+            right, bottom, 1.0, 0.0,
+            left, bottom, 0.0, 0.0,
+            left, top, 0.0, 1.0,
+            right, top, 1.0, 1.0,
+        ]);
+    }
+
+    /// Triangles of (x, y, texcoord x, texcoord y) vertices
+    pub fn triangles(&mut self, target: GLuint, fill: Fill, color: (f32, f32, f32, f32), vertices: &[f32]) {
+        if vertices.is_empty() {
+            return;
+        }
+        self.set_target(target);
+
+        let bounds = vertices.chunks_exact(4).fold((f32::MAX, f32::MAX, f32::MIN, f32::MIN), |b, vertex| {
+            (b.0.min(vertex[0]), b.1.min(vertex[1]), b.2.max(vertex[0]), b.3.max(vertex[1]))
+        });
+        let pipeline = fill.pipeline();
+        let texture = fill.texture();
+
+        // the latest batch the triangles can join without getting under a later one
+        let mut joined = None;
+        for (index, batch) in self.batches.iter().enumerate().rev() {
+            if let Some(slot) = batch.slot(pipeline, texture) {
+                joined = Some((index, slot));
+                break;
+            }
+            if batch.overlaps(bounds) {
+                break;
+            }
+        }
+        let (index, slot) = joined.unwrap_or_else(|| {
+            self.batches.push(Batch { pipeline, textures: vec![], vertices: vec![], bounds });
+            (self.batches.len() - 1, 0)
+        });
+
+        let batch = &mut self.batches[index];
+        if let Some(texture) = texture {
+            if slot == batch.textures.len() {
+                batch.textures.push(texture);
+            }
+        }
+        batch.bounds = (
+            batch.bounds.0.min(bounds.0), batch.bounds.1.min(bounds.1),
+            batch.bounds.2.max(bounds.2), batch.bounds.3.max(bounds.3),
+        );
+
+        let fill = [fill.kind(), slot as f32];
+        batch.vertices.reserve(vertices.len() / 4 * VERTEX_SIZE);
+        for vertex in vertices.chunks_exact(4) {
+            batch.vertices.extend_from_slice(vertex);
+            batch.vertices.extend_from_slice(&[color.0, color.1, color.2, color.3]);
+            batch.vertices.extend_from_slice(&fill);
+        }
+    }
+
+    /// Quad drawn with its own program right away, `set_uniforms` is called with the program in use
+    pub fn custom(&mut self, target: GLuint, program: Program, bounds: (f64, f64, f64, f64), set_uniforms: impl FnOnce(&gl::Gl)) {
+        self.set_target(target);
+        self.flush();
+
+        self.quad(target, bounds, Fill::Color, (0.0, 0.0, 0.0, 0.0));
+        let batches = mem::take(&mut self.batches);
+        self.draw_batches(&batches, |_| program, set_uniforms);
+    }
+
+    /// Draws the collected quads
+    pub fn flush(&mut self) {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        let batches = mem::take(&mut self.batches);
+        let pipelines = self.pipelines;
+        self.draw_batches(&batches, |batch| pipelines[batch.pipeline as usize], |_| {});
+    }
+
+    fn draw_batches(&mut self, batches: &[Batch], program: impl Fn(&Batch) -> Program, set_uniforms: impl FnOnce(&gl::Gl)) {
+        self.vertex_buf.clear();
+        for batch in batches {
+            self.vertex_buf.extend_from_slice(&batch.vertices);
+        }
+
+        unsafe {
+            self.bind_target();
+            let gl = &self.gl;
+
+            gl.BindVertexArray(self.vao);
+            gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl.BufferData(
+                gl::ARRAY_BUFFER,
+                (self.vertex_buf.len() * mem::size_of::<f32>()) as GLsizeiptr,
+                self.vertex_buf.as_ptr() as *const _,
+                gl::STREAM_DRAW,
+            );
+
+            let mut set_uniforms = Some(set_uniforms);
+            let mut first = 0;
+            for batch in batches {
+                let program = program(batch);
+                gl.UseProgram(program.id);
+                gl.Uniform1f(program.y_ratio, get_surface_y_ratio() as f32);
+                if let Some(set_uniforms) = set_uniforms.take() {
+                    set_uniforms(gl);
+                }
+                for (slot, texture) in batch.textures.iter().enumerate() {
+                    gl.ActiveTexture(gl::TEXTURE0 + slot as GLuint);
+                    gl.BindTexture(gl::TEXTURE_2D, *texture);
+                }
+
+                let count = (batch.vertices.len() / VERTEX_SIZE) as GLsizei;
+                gl.DrawArrays(gl::TRIANGLES, first, count);
+                first += count;
+            }
+        }
+    }
+
+    /// Called before the target texture is deleted, its name can be reused by a new texture
+    pub fn release_target(&mut self, target: GLuint) {
+        if self.target == target {
+            self.batches.clear();
+            self.target = 0;
+        }
+        if self.attached == target {
+            unsafe {
+                self.gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+                self.gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, 0, 0);
+            }
+            self.attached = 0;
+        }
+    }
+
+    fn set_target(&mut self, target: GLuint) {
+        if target != self.target {
+            self.flush();
+            self.target = target;
+        }
+    }
+
+    unsafe fn bind_target(&mut self) {
+        let gl = &self.gl;
+        gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        if self.attached != self.target {
+            gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.target, 0);
+            self.attached = self.target;
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::{Arc};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::Ordering;
 use std::time::Instant;
+use crate::format;
 use crate::render::{gl, SURFACE_HEIGHT, SURFACE_WIDTH};
 use crate::render::fonts::get_font;
 use crate::render::images::{get_gif, get_image};
@@ -14,23 +15,25 @@ use crate::render::screens::{ScreenManagementCmd, ScreenRendering, ScreenTrait};
 
 use crate::render::utils::circle_animation::CircleAnimation;
 use crate::render::utils::position::{FixedPosition, FreePosition};
+use crate::render::widgets::{contains, Bounds, Container};
+use crate::render::widgets::icon_button::IconButton;
 
 
 use std::sync::Mutex;
-use jni::JNIEnv;
-use jni::objects::JClass;
-use jni::sys::jdouble;
 use lazy_static::lazy_static;
-use log::{info, warn};
+use log::info;
 use crate::render::screens::paused_screen::PausedScreen;
-
-#[derive(Clone)]
-pub struct LocationMetric {
-    latitude: f64,
-    longitude: f64,
-    accuracy: f64,
-    timestamp: f64,
-}
+use crate::render::utils::clock;
+use crate::settings::{settings, UnitSystem};
+use crate::geo;
+use crate::geo::LocalFrame;
+use crate::track::{LocationMetric, Track};
+use crate::tracking::autopause::{AutoPause, MotionState};
+use crate::tracking::kalman::{KalmanFilter, SmoothedFix};
+use crate::tracking::pace::PaceWindow;
+use crate::tracking::splits::{Split, SplitTracker};
+use crate::tracking::TrackingConfig;
+use crate::tracking::validation::{FixValidator, RejectedFix};
 
 
 pub struct GpsData {
@@ -38,27 +41,54 @@ pub struct GpsData {
     gps_acc_good: bool,
     last_known_acc: Option<f64>,
     initial_metric: Option<LocationMetric>,
-    all_metrics: Vec<LocationMetric>,
-    total_time: f64,
+    frame: Option<LocalFrame>,
+    config: TrackingConfig,
+    filter: KalmanFilter,
+    validator: FixValidator,
+    autopause: AutoPause,
+    splits: SplitTracker,
+    pace_window: PaceWindow,
+    /// Smoothed fixes, one list per track segment, all segments share the frame of the initial metric.
+    smoothed_segments: Vec<Vec<SmoothedFix>>,
+    track: Track,
+    /// Timestamps of the first and the last accepted fix
+    started_at: Option<f64>,
+    last_fix_at: Option<f64>,
+    /// Time spent moving: pauses, auto-pauses and gaps between segments are excluded
+    moving_time: f64,
     total_distance: f64,
     paused: bool,
 }
 
 impl GpsData {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
+        Self::with_config(TrackingConfig::default())
+    }
+
+    pub(crate) fn with_config(config: TrackingConfig) -> Self {
         GpsData {
+            filter: KalmanFilter::new(config.kalman.clone()),
+            validator: FixValidator::new(config.validation.clone()),
+            autopause: AutoPause::new(config.autopause.clone()),
+            splits: SplitTracker::new(config.splits.clone()),
+            pace_window: PaceWindow::new(config.pace.clone()),
+            config,
             available_since: None,
             initial_metric: None,
+            frame: None,
             gps_acc_good: false,
             last_known_acc: None,
-            all_metrics: Vec::new(),
-            total_time: 0.0,
+            smoothed_segments: Vec::new(),
+            track: Track::new(),
+            started_at: None,
+            last_fix_at: None,
+            moving_time: 0.0,
             total_distance: 0.0,
             paused: false
         }
     }
 
-    fn update_location(&mut self, latitude: f64, longitude: f64, accuracy: f64, timestamp: f64) {
+    pub(crate) fn update_location(&mut self, metric: LocationMetric) {
         if self.paused {
             return;
         }
@@ -67,80 +97,167 @@ impl GpsData {
             self.available_since = Some(Instant::now());
         }
 
-        let metric = LocationMetric {
-            latitude,
-            longitude,
-            accuracy,
-            timestamp,
-        };
-
-
-        self.last_known_acc = Some(accuracy);
+        self.last_known_acc = Some(metric.accuracy);
         if let Some(available_since) = &self.available_since {
             let elapsed = Instant::now().duration_since(*available_since).as_secs_f64();
-            if elapsed < 10.0 {
+            if elapsed < self.config.validation.warmup {
                 return;
             }
         }
 
-        if accuracy <= 5.5 {
-            self.gps_acc_good = true;
-            if self.initial_metric.is_none(){
-                self.initial_metric = Some(metric.clone());
-                info!("Initial metric recorded! Training is started");
-            }
+        self.push_fix(metric);
+    }
 
-            if let Some(initial_metric) = &self.initial_metric {
-                let lat_offset = (metric.latitude - initial_metric.latitude) * 111_319.5;
-                let lon_offset = (metric.longitude - initial_metric.longitude) * (111_319.5 * initial_metric.latitude.to_radians().cos());
+    /// Distance and time accounting for a fix, without the warm-up gating of live updates.
+    /// Also used to replay imported tracks.
+    pub(crate) fn push_fix(&mut self, metric: LocationMetric) {
+        if let Err(reason) = self.validator.check(&metric) {
+            self.track.rejected.push(RejectedFix {
+                fix: metric,
+                reason,
+            });
+            return;
+        }
 
+        self.gps_acc_good = metric.accuracy <= self.config.validation.good_accuracy;
+        if self.initial_metric.is_none() {
+            // training starts only from a precise fix
+            if !self.gps_acc_good {
+                return;
+            }
+            self.initial_metric = Some(metric.clone());
+            self.frame = Some(LocalFrame::new(metric.latitude, metric.longitude));
+            info!("Initial metric recorded! Training is started");
+        }
 
-                if let Some(prev_metric) = self.all_metrics.last() {
-                    let distance = ((lat_offset - prev_metric.latitude).powi(2) + (lon_offset - prev_metric.longitude).powi(2)).sqrt();
-                    self.total_distance += distance;
+        let Some(frame) = &self.frame else {
+            return;
+        };
 
-                    let time_diff = metric.timestamp - prev_metric.timestamp;
-                    self.total_time += time_diff;
+        // offsets in meters, east/north of the initial metric
+        let (east, north) = frame.project(metric.latitude, metric.longitude);
+        let Some(smoothed) = self.filter.update(east, north, metric.accuracy, metric.timestamp) else {
+            info!("Fix ignored, accuracy {} is too low", metric.accuracy);
+            return;
+        };
+        let (latitude, longitude) = frame.unproject(smoothed.east, smoothed.north);
+
+        // intervals already accounted in the old state are moved to the new one
+        if let Some(change) = self.autopause.update(smoothed.speed(), metric.timestamp) {
+            let (distance, time) = self.accounted_since(change.since);
+            match change.state {
+                MotionState::Stopped => {
+                    // not across a split taken meanwhile, the splits would disagree with the totals
+                    let (committed_distance, committed_time) = self.splits.committed();
+                    self.total_distance -= distance.min(self.total_distance - committed_distance).max(0.0);
+                    self.moving_time -= time.min(self.moving_time - committed_time).max(0.0);
                 }
-                info!("Offset: Lat: {}, Lon: {}", lat_offset, lon_offset);
+                MotionState::Moving => {
+                    self.total_distance += distance;
+                    self.moving_time += time;
+                }
+            }
+        }
 
-                self.all_metrics.push(LocationMetric {
-                    latitude: lat_offset,
-                    longitude: lon_offset,
-                    accuracy: metric.accuracy,
-                    timestamp: metric.timestamp,
-                });
+        // the first point of a segment isn't connected to the previous segment
+        if let Some(prev) = self.smoothed_segments.last().and_then(|s| s.last()) {
+            // standing still: the track is kept, but the clock is stopped
+            if !self.autopause.is_paused() {
+                let (prev_latitude, prev_longitude) = frame.unproject(prev.east, prev.north);
+                let distance = geo::haversine_distance(prev_latitude, prev_longitude, latitude, longitude);
+                self.total_distance += distance;
 
-                info!("\nTotal time: {}, total distance: {}", self.total_time, self.total_distance);
-                info!("\nAvg speed: {}", self.avg_speed());
+                let time_diff = metric.timestamp - prev.timestamp;
+                self.moving_time += time_diff;
             }
         }
+        info!("Offset: Lat: {}, Lon: {}", smoothed.north, smoothed.east);
+
+        self.track.push(metric.clone());
+        self.started_at.get_or_insert(metric.timestamp);
+        self.last_fix_at = Some(metric.timestamp);
+
+        match self.smoothed_segments.last_mut() {
+            Some(segment) => segment.push(smoothed),
+            None => self.smoothed_segments.push(vec![smoothed]),
+        }
+
+        self.splits.update(self.total_distance, self.moving_time, metric.altitude);
+        if self.autopause.is_paused() {
+            self.pace_window.reset();
+        }
         else {
-            self.gps_acc_good = false;
+            self.pace_window.push(metric.timestamp, self.total_distance);
+        }
+
+        info!("\nMoving time: {}, total distance: {}", self.moving_time, self.total_distance);
+        info!("\nAvg speed: {}", self.avg_speed());
+    }
+
+    /// Distance and time of the intervals of the current segment ending at or after `since`
+    fn accounted_since(&self, since: f64) -> (f64, f64) {
+        let (Some(frame), Some(segment)) = (&self.frame, self.smoothed_segments.last()) else {
+            return (0.0, 0.0);
+        };
+
+        let mut distance = 0.0;
+        let mut time = 0.0;
+        for pair in segment.windows(2).filter(|p| p[1].timestamp >= since) {
+            let (lat1, lon1) = frame.unproject(pair[0].east, pair[0].north);
+            let (lat2, lon2) = frame.unproject(pair[1].east, pair[1].north);
+            distance += geo::haversine_distance(lat1, lon1, lat2, lon2);
+            time += pair[1].timestamp - pair[0].timestamp;
         }
+        (distance, time)
     }
 
     fn has_initial_metric(&self) -> bool {
         self.initial_metric.is_some()
     }
 
+    /// None while the location provider is disabled
+    pub(crate) fn set_available_since(&mut self, available_since: Option<Instant>) {
+        self.available_since = available_since;
+    }
+
     fn gps_online(&self) -> bool {
         self.available_since.is_some()
     }
 
+    /// Stopped by the auto-pause detector, the training itself goes on
+    pub fn is_auto_paused(&self) -> bool {
+        self.autopause.is_paused()
+    }
+
     fn is_good_accuracy(&self) -> bool {
         self.gps_acc_good
     }
 
+    /// Average speed while moving
     pub fn avg_speed(&self) -> f64 {
-        if self.total_time == 0.0 {
+        if self.moving_time == 0.0 {
             return 0.0;
         }
-        self.total_distance / self.total_time
+        self.total_distance / self.moving_time
     }
 
-    pub fn total_time(&self) -> f64 {
-        self.total_time
+    /// Speed over the last few seconds, None while standing or right after a pause
+    pub fn current_speed(&self) -> Option<f64> {
+        if self.paused || self.autopause.is_paused() {
+            return None;
+        }
+        self.pace_window.speed()
+    }
+    pub fn moving_time(&self) -> f64 {
+        self.moving_time
+    }
+
+    /// Time from the first to the last fix, pauses included
+    pub fn elapsed_time(&self) -> f64 {
+        match (self.started_at, self.last_fix_at) {
+            (Some(start), Some(last)) => last - start,
+            _ => 0.0,
+        }
     }
 
     pub fn total_distance(&self) -> f64 {
@@ -151,12 +268,35 @@ impl GpsData {
         self.last_known_acc
     }
 
+    pub fn config(&self) -> &TrackingConfig {
+        &self.config
+    }
+
+    pub fn track(&self) -> &Track {
+        &self.track
+    }
+
+    pub fn splits(&self) -> &SplitTracker {
+        &self.splits
+    }
+
+    /// Manual lap, ends at the last accepted fix
+    pub fn lap(&mut self) -> Split {
+        self.splits.lap()
+    }
+
+    /// Closes the current segment. The frame, the track and the totals are kept,
+    /// so the training goes on from the same origin after `resume`.
     pub fn pause(&mut self) {
         self.paused = true;
-        self.initial_metric = None;
-        self.all_metrics.clear();
-        self.last_known_acc = None;
-        self.available_since = None;
+        self.track.start_segment();
+        if self.smoothed_segments.last().map(|s| !s.is_empty()).unwrap_or(false) {
+            self.smoothed_segments.push(Vec::new());
+        }
+        // velocity doesn't carry over the gap
+        self.filter.reset();
+        self.autopause.reset();
+        self.pace_window.reset();
     }
 
     pub(crate) fn resume(&mut self) {
@@ -168,41 +308,76 @@ lazy_static! {
     pub static ref GPS_DATA: Mutex<GpsData> = Mutex::new(GpsData::new());
 }
 
-#[no_mangle]
-pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onLocationUpdate(
-    _env: JNIEnv,
-    _class: JClass,
-    latitude: jdouble,
-    longitude: jdouble,
-    acc: jdouble,
-    timestamp: jdouble
-) {
-    // Handle the location update
-    println!("Received location update:\n{}:  Lat {}, Lon {}. Acc: {}", timestamp,  latitude, longitude, acc);
-    let mut gps_data = GPS_DATA.lock().unwrap();
-    gps_data.update_location(latitude, longitude, acc, timestamp);
+/// Tab height of the tab shader
+const TAB_EAR_HEIGHT: f64 = 0.1;
+const TAB_EAR_WIDTH: f64 = 0.25;
+const TABS_TOP: f64 = 1.3;
+
+/// One of the "total", "cur" and "avg" tabs: two values with units under a labeled tab
+struct TrainingTab {
+    tab: Tab,
+    /// left side of the tab ear
+    offset: f64,
+    label: TextBox,
+
+    left_val: TextBox,
+    left_units: TextBox,
+    right_val: TextBox,
+    right_units: TextBox,
 }
 
+impl TrainingTab {
+    fn new(gl: Arc<gl::Gl>, color: (f32, f32, f32), offset: f64, label: &str, units: (&'static str, &'static str)) -> Self {
+        let sparky_stones = get_font("sparky-stones").unwrap();
+        let queensides = get_font("queensides").unwrap();
+
+        let pos = FreePosition::new().bottom(TABS_TOP - 1.8).left(0.0).width(1.0).height(1.8);
+        let tab = Tab::new(gl.clone(), color, pos, offset as f32);
+
+        let label = TextBox::new(gl.clone(), sparky_stones, label.to_string(), (offset as f32 + 0.05, 1.21), 0.5, 0);
+
+        let left_val = TextBox::new(gl.clone(), queensides.clone(), "-".to_string(), (0.1, 1.05), 1.0, 0);
+        let left_units = TextBox::new(gl.clone(), queensides.clone(), units.0.to_string(), (0.1, 0.95), 1.0, 0);
+        let right_val = TextBox::new(gl.clone(), queensides.clone(), "-".to_string(), (0.75, 1.05), 1.0, 0);
+        let right_units = TextBox::new(gl, queensides, units.1.to_string(), (0.76, 0.95), 1.0, 0);
+
+        TrainingTab {
+            tab,
+            offset,
+            label,
+
+            left_val,
+            left_units,
+            right_val,
+            right_units,
+        }
+    }
 
-#[no_mangle]
-pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onProviderEnabled(
-    _env: JNIEnv,
-    _class: JClass,
-) {
-    info!("GPS provider enabled!");
-    let mut gps_data = GPS_DATA.lock().unwrap();
-    gps_data.available_since = Some(Instant::now());
+    fn ear_bounds(&self) -> Bounds {
+        (self.offset, TABS_TOP - TAB_EAR_HEIGHT, TAB_EAR_WIDTH, TAB_EAR_HEIGHT)
+    }
+
+    fn set_values(&mut self, left: String, right: String) {
+        self.left_val.set_text(left);
+        self.right_val.set_text(right);
+    }
+
+    fn draw_values(&mut self, texture_id: u32) {
+        self.left_val.draw(texture_id);
+        self.left_units.draw(texture_id);
+        self.right_val.draw(texture_id);
+        self.right_units.draw(texture_id);
+    }
 }
 
+const TOTAL_TAB: usize = 0;
+const CURRENT_TAB: usize = 1;
+const AVERAGE_TAB: usize = 2;
 
-#[no_mangle]
-pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onProviderDisabled(
-    _env: JNIEnv,
-    _class: JClass,
-) {
-    warn!("GPS provider disabled!");
-    let mut gps_data = GPS_DATA.lock().unwrap();
-    gps_data.available_since = None;
+#[derive(Clone, Copy)]
+enum Action {
+    Pause,
+    Lap,
 }
 
 pub struct ActiveTrainingScreen {
@@ -211,76 +386,62 @@ pub struct ActiveTrainingScreen {
 
     screen_rendering: ScreenRendering,
 
-    play: Image,
-    walking_gif: AnimatedImage,
+    /// play icon pausing the training and the walking runner taking laps
+    controls: Container<Action>,
 
-    exit_request: Arc<AtomicBool>,
     start: Instant,
 
     gps_text: TextBox,
     gps_acc_text: TextBox,
+    /// latest split or lap
+    split_text: TextBox,
+    /// numbers of splits and laps already shown by `split_text`
+    shown_splits: (usize, usize),
 
-    tab1: Tab,
-    tab2: Tab,
-    tab3: Tab,
-
-    tab_label_1: TextBox,
-    tab_label_2: TextBox,
-    tab_label_3: TextBox,
+    /// total, cur and avg. Only the front one shows its values, others show their ears
+    tabs: Vec<TrainingTab>,
+    front_tab: usize,
 
-    //total
-    total_time_val: TextBox,
-    total_time_units: TextBox,
-
-    total_dist_val: TextBox,
-    total_dist_units: TextBox,
+    /// taken once, settings can't be changed during a training
+    units: UnitSystem,
 }
 
 impl ActiveTrainingScreen {
-    pub fn new(gl: Arc<gl::Gl>, exit_request: Arc<AtomicBool>) -> Self {
-        let squad = Squad::new_bg(gl.clone(), (0.4, 0.3, 0.5));
+    pub fn new(gl: Arc<gl::Gl>) -> Self {
+        let squad = Squad::new_bg(settings().theme.background((0.4, 0.3, 0.5)));
 
         let dims = (SURFACE_WIDTH.load(Ordering::Relaxed), SURFACE_HEIGHT.load(Ordering::Relaxed));
 
         let circ_anim = CircleAnimation::new(1.0, [(0.5, 0.5, 0.5), (-0.5, -0.2, 0.0), (0.0, 2.0, 3.0)]);
         let screen_rendering = ScreenRendering::new(gl.clone(), dims, circ_anim);
 
-        let sparky_stones = get_font("sparky-stones").unwrap();
         let queensides = get_font("queensides").unwrap();
 
-        let mut pos = FreePosition::new().bottom(-0.5).left(0.0).width(1.0)
-            .height(1.8);
-        let tab1 = Tab::new(gl.clone(), (0.05, 0.2, 0.3), pos, 0.2);
+        let settings = settings();
+        let units = settings.units;
+        let tabs = vec![
+            TrainingTab::new(gl.clone(), (0.05, 0.2, 0.3), 0.2, "total", ("time", units.long_label())),
+            TrainingTab::new(gl.clone(), (0.15, 0.1, 0.3), 0.42, "cur", (units.pace_label(), units.speed_label())),
+            TrainingTab::new(gl.clone(), (0.3, 0.05, 0.3), 0.64, "avg", (units.pace_label(), units.speed_label())),
+        ];
 
-        pos = pos.height(1.45);
-        let tab2 = Tab::new(gl.clone(), (0.15, 0.1, 0.3), pos, 0.4);
-
-        pos = pos.height(1.1);
-        let tab3 = Tab::new(gl.clone(), (0.3, 0.05, 0.3), pos, 0.6);
-
-        let tab_label_1 = TextBox::new(gl.clone(), sparky_stones.clone(), "total".to_string(), (0.25, 1.21), 0.5, 0);
-        let tab_label_2 = TextBox::new(gl.clone(), sparky_stones.clone(), "cur".to_string(), (0.47, 0.86), 0.5, 0);
-        let tab_label_3 = TextBox::new(gl.clone(), sparky_stones.clone(), "avg".to_string(), (0.67, 0.51), 0.5, 0);
-
-        let play = Image::new(gl.clone(), get_image("play").unwrap(),
+        let play = Image::new(get_image("play").unwrap(),
                               FixedPosition::new().bottom(1.7).width(0.25).left(0.15), Some((0.1, 0.9, 0.3)));
-        let walking_gif = AnimatedImage::new(gl.clone(), get_gif("walking").unwrap(),
+        let walking_gif = AnimatedImage::new(get_gif("walking").unwrap(),
                                              FixedPosition::new().bottom(1.7).width(0.55).left(0.45), 0.08);
-
-        let total_time_val = TextBox::new(gl.clone(), queensides.clone(), "-".to_string(), (0.1, 1.05), 1.0, 0);
-        let total_time_units = TextBox::new(gl.clone(), queensides.clone(), "min:sec".to_string(), (0.1, 0.95), 1.0, 0);
-
-        let total_dist_val = TextBox::new(gl.clone(), queensides.clone(), "-".to_string(), (0.75, 1.05), 1.0, 0);
-        let total_dist_units = TextBox::new(gl.clone(), queensides.clone(), "m".to_string(), (0.76, 0.95), 1.0, 0);
+        let mut controls = Container::new();
+        controls.add_tappable(IconButton::new(play, FreePosition::new().left(0.15).bottom(1.7).width(0.25).height(0.25)), Action::Pause);
+        controls.add_tappable(walking_gif, Action::Lap);
 
         let gps_text = TextBox::new(gl.clone(), queensides.clone(), "GPS status: waiting...".to_string(), (0.03, 1.55), 0.8, 1);
 
         let gps_acc_text = TextBox::new(gl.clone(), queensides.clone(), "ACC: unknown".to_string(), (0.03, 1.45), 0.6, 0);
+        let split_text = TextBox::new(gl.clone(), queensides.clone(), String::new(), (0.03, 1.37), 0.6, 0);
 
 
         //reset training
         let mut gps_data = GPS_DATA.lock().unwrap();
-        *gps_data = GpsData::new();
+        *gps_data = GpsData::with_config(settings.tracking_config());
 
 
 
@@ -288,79 +449,111 @@ impl ActiveTrainingScreen {
             gl,
             bg_squad: squad,
 
-            exit_request,
-            start: Instant::now(),
+            start: clock::now(),
             screen_rendering,
 
             gps_text,
             gps_acc_text,
+            split_text,
+            shown_splits: (0, 0),
 
-            tab1,
-            tab2,
-            tab3,
-
-            tab_label_1,
-            tab_label_2,
-            tab_label_3,
+            tabs,
+            front_tab: TOTAL_TAB,
 
-            play,
-            walking_gif,
+            units,
 
-            total_time_val,
-            total_time_units,
-            total_dist_val,
-            total_dist_units,
+            controls,
         }
     }
 }
 
 impl ScreenTrait for ActiveTrainingScreen {
     fn press(&mut self, pos: (f64, f64)) -> ScreenManagementCmd {
-        if pos.1 > 1.7 && pos.1 < 1.95 && pos.0 > 0.15 && pos.0 < 0.4  {
-            let mut gps_data = GPS_DATA.lock().unwrap();
-            gps_data.pause();
-            return ScreenManagementCmd::PushScreen(Box::new(PausedScreen::new(self.gl.clone(), self.exit_request.clone())));
+        match self.controls.press(pos) {
+            Some(Action::Pause) => {
+                let mut gps_data = GPS_DATA.lock().unwrap();
+                gps_data.pause();
+                return ScreenManagementCmd::PushScreen(Box::new(PausedScreen::new(self.gl.clone())));
+            }
+            Some(Action::Lap) => {
+                let mut gps_data = GPS_DATA.lock().unwrap();
+                if gps_data.has_initial_metric() {
+                    gps_data.lap();
+                }
+            }
+            None => {}
+        }
+
+        // ears overlap a bit, the front one wins
+        if !contains(self.tabs[self.front_tab].ear_bounds(), pos) {
+            if let Some(i) = (0..self.tabs.len()).rev().find(|i| contains(self.tabs[*i].ear_bounds(), pos)) {
+                self.front_tab = i;
+            }
         }
         ScreenManagementCmd::None
     }
     fn back(&mut self) -> ScreenManagementCmd {
         let mut gps_data = GPS_DATA.lock().unwrap();
         gps_data.pause();
-        return ScreenManagementCmd::PushScreen(Box::new(PausedScreen::new(self.gl.clone(), self.exit_request.clone())));
+        ScreenManagementCmd::PushScreen(Box::new(PausedScreen::new(self.gl.clone())))
     }
     fn update(&mut self) -> ScreenManagementCmd {
         let gps_data = GPS_DATA.lock().unwrap();
 
         if gps_data.gps_online() {
             if gps_data.has_initial_metric() {
-                if gps_data.is_good_accuracy() {
+                if gps_data.is_auto_paused() {
+                    self.gps_text.set_text("GPS status: auto-paused".to_string());
+                }
+                else if gps_data.is_good_accuracy() {
                     self.gps_text.set_text("GPS status: training online".to_string());
-                    self.total_dist_val.set_text(format!("{:.2}", gps_data.total_distance()));
                 }
                 else {
                     self.gps_text.set_text("GPS status: training online (bad acc)".to_string());
                 }
 
-                let total_time = gps_data.total_time();
-                let secs = total_time as u64;
-                let mins = secs / 60;
-                let secs = secs % 60;
-                self.total_time_val.set_text(format!("{:02}:{:02}", mins, secs));
+                let units = self.units;
+                self.tabs[TOTAL_TAB].set_values(format::duration(gps_data.moving_time()),
+                                                format::distance(gps_data.total_distance(), units));
+
+                let current_speed = gps_data.current_speed().unwrap_or(0.0);
+                self.tabs[CURRENT_TAB].set_values(format::speed_pace(current_speed, units), format::speed(current_speed, units));
+
+                let avg_speed = gps_data.avg_speed();
+                self.tabs[AVERAGE_TAB].set_values(format::speed_pace(avg_speed, units), format::speed(avg_speed, units));
             }
             else {
                 self.gps_text.set_text("GPS status: waiting (bad acc)".to_string());
             }
 
             if gps_data.is_good_accuracy() {
-                self.gps_acc_text.set_text(format!("ACC: +-{:.2}m", gps_data.get_last_known_acc().unwrap()));
+                self.gps_acc_text.set_text(format!("ACC: +-{}{}", format::short_distance(gps_data.get_last_known_acc().unwrap(), self.units), self.units.short_label()));
             }
             else {
-                self.gps_acc_text.set_text(format!("ACC: +-{:.2}m (not enough)", gps_data.get_last_known_acc().unwrap()));
+                self.gps_acc_text.set_text(format!("ACC: +-{}{} (not enough)", format::short_distance(gps_data.get_last_known_acc().unwrap(), self.units), self.units.short_label()));
             }
         }
         else {
             self.gps_text.set_text("GPS status: offline".to_string());
         }
+
+        let splits = gps_data.splits();
+        let counts = (splits.splits().len(), splits.laps().len());
+        if counts != self.shown_splits {
+            // a lap is taken by the runner, so it is more interesting than a split at the same time
+            let text = if counts.1 > self.shown_splits.1 {
+                splits.laps().last().map(|lap| format!("Lap {}: {}{} in {}", lap.number, format::short_distance(lap.distance, self.units),
+                                                       self.units.short_label(), format::duration(lap.time)))
+            }
+            else {
+                splits.splits().last().map(|split| format!("{} {}: {} /{}", self.units.long_label(), split.number,
+                                                           format::pace_of(split.distance, split.time, self.units), self.units.long_label()))
+            };
+            if let Some(text) = text {
+                self.split_text.set_text(text);
+            }
+            self.shown_splits = counts;
+        }
         ScreenManagementCmd::None
     }
     fn draw(&mut self) {
@@ -369,24 +562,23 @@ impl ScreenTrait for ActiveTrainingScreen {
 
         self.bg_squad.draw(texture_id);
 
-        self.play.draw(texture_id);
-        self.walking_gif.draw(texture_id);
+        self.controls.draw(texture_id);
 
         self.gps_text.draw(texture_id);
         self.gps_acc_text.draw(texture_id);
+        self.split_text.draw(texture_id);
 
-        self.tab1.draw(texture_id);
-        self.tab2.draw(texture_id);
-        self.tab3.draw(texture_id);
-
-        self.tab_label_1.draw(texture_id);
-        self.tab_label_2.draw(texture_id);
-        self.tab_label_3.draw(texture_id);
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            if i != self.front_tab {
+                tab.tab.draw(texture_id);
+            }
+        }
+        self.tabs[self.front_tab].tab.draw(texture_id);
 
-        self.total_time_val.draw(texture_id);
-        self.total_time_units.draw(texture_id);
-        self.total_dist_val.draw(texture_id);
-        self.total_dist_units.draw(texture_id);
+        for tab in self.tabs.iter_mut() {
+            tab.label.draw(texture_id);
+        }
+        self.tabs[self.front_tab].draw_values(texture_id);
 
         self.screen_rendering.present();
     }
@@ -394,6 +586,122 @@ impl ScreenTrait for ActiveTrainingScreen {
 
     }
     fn is_expanded(&self) -> bool {
-        Instant::now().duration_since(self.start).as_secs_f32() > 1.0
+        clock::elapsed(self.start).as_secs_f32() > 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tracking::autopause::AutoPauseConfig;
+    use super::*;
+
+    /// Fix `north` meters north of the start, one per second
+    fn fix(north: f64, timestamp: f64) -> LocationMetric {
+        LocationMetric {
+            latitude: 59.9 + north / 111_320.0,
+            longitude: 30.3,
+            accuracy: 3.0,
+            timestamp,
+            altitude: None,
+        }
+    }
+
+    /// Positions of a run at `speed` m/s with a stand of `stand` seconds after `run` seconds, then running again
+    fn run_with_stop(speed: f64, run: usize, stand: usize, run_again: usize) -> Vec<f64> {
+        let mut positions = vec![];
+        let mut north = 0.0;
+        for i in 0..run + stand + run_again {
+            positions.push(north);
+            if i < run || i >= run + stand {
+                north += speed;
+            }
+        }
+        positions
+    }
+
+    /// (total distance, moving time, auto-paused) after every fix
+    fn replay(gps_data: &mut GpsData, positions: &[f64]) -> Vec<(f64, f64, bool)> {
+        positions.iter().enumerate().map(|(i, north)| {
+            gps_data.push_fix(fix(*north, i as f64));
+            (gps_data.total_distance(), gps_data.moving_time(), gps_data.is_auto_paused())
+        }).collect()
+    }
+
+    /// Index of the first state after the auto-pause switched to `paused`
+    fn switch_to(states: &[(f64, f64, bool)], paused: bool) -> usize {
+        let from = if paused { 0 } else { states.iter().position(|s| s.2).unwrap() };
+        from + states[from..].iter().position(|s| s.2 == paused).unwrap()
+    }
+
+    #[test]
+    fn stops_are_not_accounted() {
+        let positions = run_with_stop(3.0, 60, 20, 30);
+        let states = replay(&mut GpsData::new(), &positions);
+
+        // the time before the stop was confirmed is taken back
+        let stop = switch_to(&states, true);
+        let stop_delay = AutoPauseConfig::default().stop_delay;
+        assert!(states[stop].1 <= states[stop - 1].1 - stop_delay);
+        assert!(states[stop].0 < states[stop - 1].0);
+
+        // nothing is accounted while standing
+        let resume = switch_to(&states, false);
+        assert!(states[stop..resume].iter().all(|s| *s == states[stop]));
+
+        // and the time before the run was confirmed is added back
+        let resume_delay = AutoPauseConfig::default().resume_delay;
+        assert!(states[resume].1 >= states[resume - 1].1 + resume_delay);
+
+        let (distance, time, _) = *states.last().unwrap();
+        assert!((distance - 89.0 * 3.0).abs() < 5.0, "{}", distance);
+        assert!((time - 89.0).abs() < 5.0, "{}", time);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn pause_is_not_in_the_split() {
+        let mut config = TrackingConfig::default();
+        config.splits.split_distance = 200.0;
+        let mut gps_data = GpsData::with_config(config);
+
+        for i in 0..50 {
+            gps_data.push_fix(fix(3.0 * i as f64, i as f64));
+        }
+        gps_data.pause();
+        gps_data.resume();
+        // resumed 100 s later a bit further
+        for i in 0..50 {
+            gps_data.push_fix(fix(160.0 + 3.0 * i as f64, 150.0 + i as f64));
+        }
+
+        let split = &gps_data.splits().splits()[0];
+        assert_eq!(split.end_distance, 200.0);
+        assert!((split.time - 200.0 / 3.0).abs() < 3.0, "{}", split.time);
+        assert!((gps_data.moving_time() - 98.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stop_doesnt_take_back_a_split() {
+        let positions = run_with_stop(3.0, 60, 20, 30);
+
+        // distance before and after the stop is taken back
+        let states = replay(&mut GpsData::new(), &positions);
+        let stop = switch_to(&states, true);
+        let (before, after) = (states[stop - 1].0, states[stop].0);
+        assert!(after < before);
+
+        // a split ends in between
+        let mut config = TrackingConfig::default();
+        config.splits.split_distance = (before + after) / 2.0;
+        let mut gps_data = GpsData::with_config(config);
+        let states = replay(&mut gps_data, &positions);
+
+        let split = &gps_data.splits().splits()[0];
+        assert!(states[stop..].iter().all(|s| s.0 >= split.end_distance && s.1 >= split.time));
+
+        let splits = gps_data.splits().final_splits();
+        let distance: f64 = splits.iter().map(|s| s.distance).sum();
+        let time: f64 = splits.iter().map(|s| s.time).sum();
+        assert!((distance - gps_data.total_distance()).abs() < 1e-6);
+        assert!((time - gps_data.moving_time()).abs() < 1e-6);
+    }
+}
@@ -3,20 +3,35 @@ pub mod records;
 pub mod stats;
 pub mod active_training;
 pub mod paused_screen;
+pub mod record_detail;
+pub mod settings;
 
 
 use std::sync::{Arc};
+use std::sync::atomic::Ordering;
 use log::{info};
-use crate::render::{create_shader, gl};
+use crate::render::{gl, PRESENT_FRAMEBUFFER};
 
 use crate::render::gl::types::{GLint, GLsizeiptr, GLuint};
 use crate::render::objects::SQUAD_VERTEX_DATA;
+use crate::render::renderer::{with_renderer, Program};
+use crate::render::shader::ShaderSource;
+use crate::shader_source;
 use crate::render::utils::circle_animation::CircleAnimation;
+use crate::render::widgets::nav_bar::NavTab;
 
 pub enum ScreenManagementCmd {
     None,
+    /// Shows a screen over the current one, popping it goes back
     PushScreen(Box<dyn ScreenTrait>),
-    PopScreen
+    PopScreen,
+    /// Shows a screen instead of the current one
+    ReplaceScreen(Box<dyn ScreenTrait>),
+    /// Goes back to the bottom screen
+    PopToRoot,
+    /// Shows the screen of a bottom bar tab instead of all the current ones,
+    /// the screen is reused if the tab was shown before
+    SwitchTab(NavTab),
 }
 pub trait ScreenTrait {
     fn start_scroll(&mut self, _pos: (f64, f64)) -> bool {
@@ -36,6 +51,8 @@ pub trait ScreenTrait {
     fn update(&mut self) -> ScreenManagementCmd {
         ScreenManagementCmd::None
     }
+    /// Called when the screen is shown again: the screens over it are gone or its tab is switched back to
+    fn resume(&mut self) {}
     fn draw(&mut self);
 
     fn is_expanded(&self) -> bool {
@@ -44,40 +61,29 @@ pub trait ScreenTrait {
 }
 
 pub struct ScreenRendering {
-    program: GLuint,
+    program: Program,
     vao: GLuint,
     vbo: GLuint,
-    fbo: GLuint,
     gl: Arc<gl::Gl>,
     texture: GLuint,
     dims: (u32, u32),
 
     circle: GLint,
+    y_ratio: GLint,
     circle_anim: CircleAnimation
 }
 
 
-const VERTEX_SHADER_SOURCE: &[u8] = include_bytes!("present-vert.glsl");
-const FRAGMENT_SHADER_SOURCE: &[u8] = include_bytes!("present-frag.glsl");
+const VERTEX_SHADER_SOURCE: ShaderSource = shader_source!("src/render/screens/present-vert.glsl");
+const FRAGMENT_SHADER_SOURCE: ShaderSource = shader_source!("src/render/screens/present-frag.glsl");
 
 impl ScreenRendering {
     pub fn new(gl: Arc<gl::Gl>, dims: (u32, u32), mut circle_anim: CircleAnimation) -> Self {
 
-        unsafe {
-            let vertex_shader = create_shader(&gl, gl::VERTEX_SHADER, VERTEX_SHADER_SOURCE);
-            let fragment_shader = create_shader(&gl, gl::FRAGMENT_SHADER, FRAGMENT_SHADER_SOURCE);
-
-            let program = gl.CreateProgram();
-
-            gl.AttachShader(program, vertex_shader);
-            gl.AttachShader(program, fragment_shader);
-
-            gl.LinkProgram(program);
+        let program = with_renderer(|renderer| renderer.program("present", VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE));
 
-            gl.UseProgram(program);
-
-            gl.DeleteShader(vertex_shader);
-            gl.DeleteShader(fragment_shader);
+        unsafe {
+            gl.UseProgram(program.id);
 
             let mut vao = std::mem::zeroed();
             gl.GenVertexArrays(1, &mut vao);
@@ -93,7 +99,7 @@ impl ScreenRendering {
                 gl::STATIC_DRAW,
             );
 
-            let pos_attrib = gl.GetAttribLocation(program, b"position\0".as_ptr() as *const _);
+            let pos_attrib = gl.GetAttribLocation(program.id, c"position".as_ptr());
             gl.VertexAttribPointer(
                 pos_attrib as GLuint,
                 2,
@@ -105,10 +111,6 @@ impl ScreenRendering {
             gl.EnableVertexAttribArray(pos_attrib as GLuint);
 
 
-            let mut fbo = 0;
-            gl.GenFramebuffers(1, &mut fbo);
-
-
             // Generate a texture ID
             let mut texture = std::mem::zeroed();
             gl.GenTextures(1, &mut texture);
@@ -134,14 +136,13 @@ impl ScreenRendering {
                 data.as_ptr() as *const _,
             );
 
-            let texture_loc = gl.GetUniformLocation(program, b"u_texture\0".as_ptr() as *const _);
-            gl.Uniform1i(texture_loc, 0);
+            gl.Uniform1i(program.uniform_location(&gl, c"u_texture"), 0);
 
-            let circle = gl.GetUniformLocation(program, b"u_circle\0".as_ptr() as *const _);
+            let circle = program.uniform_location(&gl, c"u_circle");
             gl.Uniform3f(circle, 0.0, 0.0, 0.0);
 
-            let y_ratio = gl.GetUniformLocation(program, b"y_ratio\0".as_ptr() as *const _);
-            gl.Uniform1f(y_ratio, dims.1 as f32 / dims.0 as f32);
+            let y_ratio = program.uniform_location(&gl, c"y_ratio");
+
             circle_anim.start();
 
 
@@ -149,12 +150,12 @@ impl ScreenRendering {
                 program,
                 vao,
                 vbo,
-                fbo,
                 gl,
                 texture,
                 dims,
 
                 circle,
+                y_ratio,
                 circle_anim
             }
         }
@@ -164,36 +165,28 @@ impl ScreenRendering {
         self.texture
     }
     pub fn clear_texture(&self) {
-        let gl = &self.gl;
-
-        unsafe {
-            gl.BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
-            gl.FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.texture, 0);
-            if gl.CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
-                panic!("Framebuffer is not complete");
-            }
-
-            let clear_color = [0.0, 0.0, 0.0, 0.0];
-            gl.ClearColor(clear_color[0], clear_color[1], clear_color[2], clear_color[3]);
-            gl.Clear(gl::COLOR_BUFFER_BIT);
-        }
+        with_renderer(|renderer| renderer.clear(self.texture));
     }
 
+    /// Draws the texture to the present framebuffer, after the quads batched for it
     pub fn present(&self) {
+        with_renderer(|renderer| renderer.flush());
+
         let gl = &self.gl;
 
         unsafe {
-            gl.UseProgram(self.program);
+            gl.UseProgram(self.program.id);
             gl.BindVertexArray(self.vao);
             gl.BindBuffer(gl::ARRAY_BUFFER, self.vbo);
 
-            gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl.BindFramebuffer(gl::FRAMEBUFFER, PRESENT_FRAMEBUFFER.load(Ordering::Relaxed));
 
             gl.ActiveTexture(gl::TEXTURE0);
             gl.BindTexture(gl::TEXTURE_2D, self.texture);// because we use this texture in rendering
 
             let circ_params = self.circle_anim.cur();
             gl.Uniform3f(self.circle, circ_params.0, circ_params.1, circ_params.2);
+            gl.Uniform1f(self.y_ratio, self.dims.1 as f32 / self.dims.0 as f32);
 
 
             gl.DrawArrays(gl::TRIANGLES, 0, 6);
@@ -205,11 +198,10 @@ impl Drop for ScreenRendering {
     fn drop(&mut self) {
         let gl = &self.gl;
 
+        with_renderer(|renderer| renderer.release_target(self.texture));
         unsafe {
-            gl.DeleteProgram(self.program);
             gl.DeleteVertexArrays(1, &self.vao);
             gl.DeleteBuffers(1, &self.vbo);
-            gl.DeleteFramebuffers(1, &self.fbo);
             gl.DeleteTextures(1, &self.texture);
         }
     }
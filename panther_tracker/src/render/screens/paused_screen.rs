@@ -1,5 +1,5 @@
 use std::sync::{Arc};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::Ordering;
 use std::time::Instant;
 use crate::render::{gl, SURFACE_HEIGHT, SURFACE_WIDTH};
 use crate::render::fonts::get_font;
@@ -13,7 +13,12 @@ use crate::render::screens::{ScreenManagementCmd, ScreenRendering, ScreenTrait};
 
 
 use crate::render::utils::circle_animation::CircleAnimation;
-use crate::render::utils::position::{FixedPosition, FreePosition};
+use crate::render::utils::position::FreePosition;
+use crate::render::utils::text_layout::{Align, TextLayout};
+use crate::render::widgets::Container;
+use crate::render::widgets::button::Button;
+use crate::render::widgets::label::Label;
+use crate::render::widgets::panel::Panel;
 
 
 use std::sync::Mutex;
@@ -23,39 +28,39 @@ use jni::sys::jdouble;
 use lazy_static::lazy_static;
 use log::{info, warn};
 use crate::render::screens::active_training::GPS_DATA;
-use crate::render::screens::main::{MainScreen, stop_location_updates};
+use crate::render::widgets::nav_bar::NavTab;
 use crate::render::screens::records::push_new_record;
+use crate::tracking::provider::with_location_provider;
+
+#[derive(Clone, Copy)]
+enum Action {
+    Finish,
+    Continue,
+}
 
 pub struct PausedScreen {
-    gl: Arc<gl::Gl>,
     bg_squad: Squad,
 
-    tab: Squad,
-
     screen_rendering: ScreenRendering,
-    exit_request: Arc<AtomicBool>,
 
-    tittle: TextBox,
-    exit_but: TextBox,
-    continue_but: TextBox,
-
-    exit_bg: Squad,
-    continue_bg: Squad,
+    /// dialog with the title and the buttons
+    dialog: Container<Action>,
 
     is_pause: bool
 }
 
 impl PausedScreen {
-    pub fn new(gl: Arc<gl::Gl>, exit_request: Arc<AtomicBool>) -> Self {
-        let bg_squad = Squad::new_bg_alpha(gl.clone(), (0.0, 0.0, 0.0, 0.5));
+    pub fn new(gl: Arc<gl::Gl>) -> Self {
+        let bg_squad = Squad::new_bg_alpha((0.0, 0.0, 0.0, 0.5));
 
         unsafe {
             gl.Enable(gl::BLEND);
             gl.BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
         }
 
-        let tab = Squad::new(gl.clone(), (0.4, 0.5, 0.9, 1.0),
-            FreePosition::new().bottom(1.1).left(0.1).width(0.8).height(0.5));
+        let mut dialog = Container::new();
+        dialog.add(Panel::new(Squad::new((0.4, 0.5, 0.9, 1.0),
+            FreePosition::new().bottom(1.1).left(0.1).width(0.8).height(0.5))));
 
         let dims = (SURFACE_WIDTH.load(Ordering::Relaxed), SURFACE_HEIGHT.load(Ordering::Relaxed));
 
@@ -64,29 +69,26 @@ impl PausedScreen {
 
         let font = get_font("queensides").unwrap();
 
-        let tittle = TextBox::new(gl.clone(), font.clone(), "Paused...".to_string(), (0.15, 1.4), 2.0, 2);
-        let exit_but = TextBox::new(gl.clone(), font.clone(), "Finish".to_string(), (0.15, 1.15), 1.2, 1);
-        let continue_but = TextBox::new(gl.clone(), font.clone(), "Continue".to_string(), (0.55, 1.15), 1.2, 1);
+        let centered = TextLayout::new().align(Align::Center);
+        let tittle = TextBox::new(gl.clone(), font.clone(), "Paused...".to_string(), (0.5, 1.4), 2.0, 2).layout(centered);
+        let exit_but = TextBox::new(gl.clone(), font.clone(), "Finish".to_string(), (0.3, 1.15), 1.2, 1).layout(centered);
+        let continue_but = TextBox::new(gl.clone(), font.clone(), "Continue".to_string(), (0.7, 1.15), 1.2, 1).layout(centered);
 
-        let exit_bg = Squad::new(gl.clone(), (0.8, 0.2, 0.2, 1.0),
+        let exit_bg = Squad::new((0.8, 0.2, 0.2, 1.0),
                 FreePosition::new().left(0.10).bottom(1.1).width(0.4).height(0.18));
-        let continue_bg = Squad::new(gl.clone(), (0.2, 0.8, 0.2, 1.0),
+        let continue_bg = Squad::new((0.2, 0.8, 0.2, 1.0),
                 FreePosition::new().left(0.5).bottom(1.1).width(0.4).height(0.18));
 
+        dialog.add_tappable(Button::new(exit_bg, exit_but), Action::Finish);
+        dialog.add_tappable(Button::new(continue_bg, continue_but), Action::Continue);
+        dialog.add(Label::new(tittle));
+
         PausedScreen {
-            gl,
             bg_squad,
-            tab,
 
-            exit_request,
             screen_rendering,
 
-            tittle,
-            exit_but,
-            continue_but,
-
-            exit_bg,
-            continue_bg,
+            dialog,
 
             is_pause: false
         }
@@ -99,22 +101,21 @@ impl PausedScreen {
 
 impl ScreenTrait for PausedScreen {
     fn press(&mut self, pos: (f64, f64)) -> ScreenManagementCmd {
-        // continue button
-        if pos.0 > 0.5 && pos.0 < 0.9 && pos.1 > 1.1 && pos.1 < 1.28 {
-            let mut gps_data = GPS_DATA.lock().unwrap();
-            gps_data.resume();
-            return ScreenManagementCmd::PopScreen;
-        }
-        // exit button
-        if pos.0 > 0.1 && pos.0 < 0.5 && pos.1 > 1.1 && pos.1 < 1.28 {
-            let mut gps_data = GPS_DATA.lock().unwrap();
-            gps_data.pause();
-            push_new_record(&gps_data);
-            stop_location_updates();
-            return ScreenManagementCmd::PushScreen(Box::new(MainScreen::new(self.gl.clone(), self.exit_request.clone())));
+        match self.dialog.press(pos) {
+            Some(Action::Continue) => {
+                let mut gps_data = GPS_DATA.lock().unwrap();
+                gps_data.resume();
+                ScreenManagementCmd::PopScreen
+            }
+            Some(Action::Finish) => {
+                let mut gps_data = GPS_DATA.lock().unwrap();
+                gps_data.pause();
+                push_new_record(&gps_data);
+                with_location_provider(|provider| provider.stop());
+                ScreenManagementCmd::SwitchTab(NavTab::Home)
+            }
+            None => ScreenManagementCmd::None,
         }
-
-        ScreenManagementCmd::None
     }
     fn back(&mut self) -> ScreenManagementCmd {
         let mut gps_data = GPS_DATA.lock().unwrap();
@@ -126,14 +127,7 @@ impl ScreenTrait for PausedScreen {
         self.screen_rendering.clear_texture();
 
         self.bg_squad.draw(texture_id);
-        self.tab.draw(texture_id);
-
-        self.exit_bg.draw(texture_id);
-        self.continue_bg.draw(texture_id);
-
-        self.tittle.draw(texture_id);
-        self.exit_but.draw(texture_id);
-        self.continue_but.draw(texture_id);
+        self.dialog.draw(texture_id);
 
         self.screen_rendering.present();
     }
@@ -0,0 +1,202 @@
+use std::sync::{Arc};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use log::error;
+use crate::format;
+use crate::render::{gl, SURFACE_HEIGHT, SURFACE_WIDTH};
+use crate::render::fonts::get_font;
+use crate::render::images::get_image;
+use crate::render::objects::image::Image;
+use crate::render::objects::r#box::Squad;
+use crate::render::objects::textbox::TextBox;
+use crate::render::screens::{ScreenManagementCmd, ScreenRendering, ScreenTrait};
+use crate::render::screens::records::Record;
+use crate::render::utils::circle_animation::CircleAnimation;
+use crate::render::utils::position::{FixedPosition, FreePosition};
+use crate::render::utils::clock;
+use crate::render::utils::text_layout::{Align, TextLayout};
+use crate::render::widgets::{Container, WidgetId};
+use crate::render::widgets::label::Label;
+use crate::render::widgets::panel::Panel;
+use crate::settings::{settings, UnitSystem};
+use crate::storage::get_storage;
+use crate::tracking::splits::{Split, SplitKind};
+
+const ROW_HEIGHT: f64 = 0.1;
+const LIST_TOP: f64 = 1.3;
+const BG_COLOR: (f32, f32, f32) = (0.6, 0.8, 0.2);
+
+#[derive(Clone, Copy)]
+enum Action {
+    ExportGpx,
+}
+
+fn split_row(split: &Split, units: UnitSystem) -> String {
+    let pace = format::pace_of(split.distance, split.time, units);
+    let elevation = split.elevation_change
+        .map(|e| format!("{}{} {}", if e > 0.0 { "+" } else { "" }, format::short_distance(e, units), units.short_label()))
+        .unwrap_or_default();
+
+    // distance splits were cut with the units of the time they were recorded
+    match split.kind {
+        SplitKind::Distance => format!("split {}   {}   {} /{}   {}", split.number, format::duration(split.time), pace, units.long_label(), elevation),
+        SplitKind::Lap => format!("lap {}   {} {}   {}   {} /{}", split.number, format::short_distance(split.distance, units), units.short_label(),
+                                  format::duration(split.time), pace, units.long_label()),
+    }
+}
+
+/// Summary of a finished training with its splits and laps
+pub struct RecordDetailScreen {
+    record: Record,
+
+    bg_squad: Squad,
+    screen_rendering: ScreenRendering,
+
+    start: Instant,
+
+    logo: Image,
+
+    title: TextBox,
+    summary: TextBox,
+
+    /// texts of the headers and split rows, a split row is drawn over `row_square`
+    rows: Vec<(TextBox, bool)>,
+    row_square: Squad,
+
+    /// bar with the export button, the rows scroll under it
+    ui: Container<Action>,
+    export_id: WidgetId<Label>,
+
+    scroll_offset: f64,
+}
+
+impl RecordDetailScreen {
+    /// `number` is the position of the record in the list, as shown on `RecordsScreen`
+    pub fn new(gl: Arc<gl::Gl>, record: Record, number: usize) -> Self {
+        let squad = Squad::new_bg(settings().theme.background(BG_COLOR));
+
+        let dims = (SURFACE_WIDTH.load(Ordering::Relaxed), SURFACE_HEIGHT.load(Ordering::Relaxed));
+
+        let circ_anim = CircleAnimation::new(1.0, [(0.5, 0.5, 0.5), (-0.5, -0.2, 0.0), (0.0, 2.0, 3.0)]);
+        let screen_rendering = ScreenRendering::new(gl.clone(), dims, circ_anim);
+
+        let font = get_font("queensides").unwrap();
+
+        let logo = Image::new(get_image("panther_logo").unwrap(),
+                              FixedPosition::new().bottom(1.75).width(0.25).left(0.65), Some((0.05, 0.06, 0.1)));
+
+        let title = TextBox::new(gl.clone(), font.clone(), format!("Record {}", number), (0.07, 1.8), 1.0, 1);
+
+        let units = settings().units;
+        let summary = TextBox::new(gl.clone(), font.clone(),
+                                   format!("{} {}\nmoving {}, total {}\navg pace {} /{}",
+                                           format::distance(record.distance, units), units.long_label(),
+                                           format::duration(record.time), format::duration(record.elapsed_time),
+                                           format::pace_of(record.distance, record.time, units), units.long_label()),
+                                   (0.07, 1.62), 0.6, 1);
+
+        let mut rows = Vec::new();
+        if !record.splits.is_empty() {
+            rows.push(("Splits".to_string(), true));
+            rows.extend(record.splits.iter().map(|s| (split_row(s, units), false)));
+        }
+        if !record.laps.is_empty() {
+            rows.push(("Laps".to_string(), true));
+            rows.extend(record.laps.iter().map(|s| (split_row(s, units), false)));
+        }
+        if rows.is_empty() {
+            rows.push(("No splits recorded".to_string(), true));
+        }
+
+        let rows = rows.into_iter().enumerate().map(|(i, (text, is_header))| {
+            let y = LIST_TOP - ROW_HEIGHT * i as f64;
+            (TextBox::new(gl.clone(), font.clone(), text, (0.1, y as f32), 0.55, 1), is_header)
+        }).collect();
+        let row_square = Squad::new((0.5, 0.3, 0.5, 1.0),
+                                    FreePosition::new().bottom(LIST_TOP - 0.03).left(0.05).width(0.9).height(ROW_HEIGHT - 0.01));
+
+        let mut ui = Container::new();
+        let (r, g, b) = settings().theme.background(BG_COLOR);
+        ui.add(Panel::new(Squad::new((r, g, b, 1.0), FreePosition::new().bottom(0.0).left(0.0).width(1.0).height(0.2))));
+        let export_text = TextBox::new(gl.clone(), font.clone(), "Export GPX".to_string(), (0.5, 0.12), 0.6, 1)
+            .layout(TextLayout::new().align(Align::Center));
+        let export_id = ui.add_tappable(Label::new(export_text).padding(0.05), Action::ExportGpx);
+
+        RecordDetailScreen {
+            record,
+
+            bg_squad: squad,
+            screen_rendering,
+
+            start: clock::now(),
+
+            logo,
+
+            title,
+            summary,
+
+            rows,
+            row_square,
+
+            ui,
+            export_id,
+
+            scroll_offset: 0.0,
+        }
+    }
+}
+
+impl ScreenTrait for RecordDetailScreen {
+    fn press(&mut self, pos: (f64, f64)) -> ScreenManagementCmd {
+        match self.ui.press(pos) {
+            Some(Action::ExportGpx) => {
+                let Some(storage) = get_storage() else {
+                    return ScreenManagementCmd::None;
+                };
+                let text = match storage.export_gpx(&self.record) {
+                    Ok(path) => format!("Saved {}", path.file_name().unwrap_or_default().to_string_lossy()),
+                    Err(err) => {
+                        error!("[Records] Failed to export record {}: {}", self.record.id, err);
+                        "Export failed".to_string()
+                    }
+                };
+                self.ui.get_mut(self.export_id).set_text(text);
+                ScreenManagementCmd::None
+            }
+            None => ScreenManagementCmd::None,
+        }
+    }
+    fn back(&mut self) -> ScreenManagementCmd {
+        ScreenManagementCmd::PopScreen
+    }
+    fn draw(&mut self) {
+        let texture_id = self.screen_rendering.texture_id();
+        self.screen_rendering.clear_texture();
+
+        self.bg_squad.draw(texture_id);
+
+        self.logo.draw(texture_id);
+
+        for (i, (text, is_header)) in self.rows.iter_mut().enumerate() {
+            if !*is_header {
+                self.row_square.set_pos_y_offset(self.scroll_offset - ROW_HEIGHT * i as f64);
+                self.row_square.draw(texture_id);
+            }
+            text.set_pos_y_offs(self.scroll_offset);
+            text.draw(texture_id);
+        }
+
+        self.title.draw(texture_id);
+        self.summary.draw(texture_id);
+        self.ui.draw(texture_id);
+
+        self.screen_rendering.present();
+    }
+    fn scroll(&mut self, pos: (f64, f64)) {
+        // the list can't be pulled below its top
+        self.scroll_offset = (self.scroll_offset + pos.1).max(0.0);
+    }
+    fn is_expanded(&self) -> bool {
+        clock::elapsed(self.start).as_secs_f32() > 1.0
+    }
+}
@@ -1,10 +1,10 @@
-use std::fs::File;
-use std::io::Write;
 use std::sync::{Arc, Mutex, MutexGuard};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::Ordering;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use lazy_static::lazy_static;
-use crate::render::{ANDROID_DATA_PATH, gl, SURFACE_HEIGHT, SURFACE_WIDTH};
+use log::error;
+use crate::format;
+use crate::render::{gl, SURFACE_HEIGHT, SURFACE_WIDTH};
 use crate::render::fonts::get_font;
 use crate::render::images::{get_image};
 
@@ -13,18 +13,38 @@ use crate::render::objects::r#box::Squad;
 use crate::render::objects::textbox::TextBox;
 use crate::render::screens::{ScreenManagementCmd, ScreenRendering, ScreenTrait};
 use crate::render::screens::active_training::{GPS_DATA, GpsData};
-use crate::render::screens::main::MainScreen;
-use crate::render::screens::stats::StatsScreen;
+use crate::render::screens::record_detail::RecordDetailScreen;
 use crate::render::utils::circle_animation::CircleAnimation;
 use crate::render::utils::position::{FixedPosition, FreePosition};
+use crate::render::utils::clock;
+use crate::render::utils::text_layout::TextLayout;
+use crate::render::widgets::{contains, Widget};
+use crate::render::widgets::nav_bar::{NavBar, NavTab};
+use crate::render::widgets::panel::Panel;
+use crate::settings::{settings, UnitSystem};
+use crate::storage::{get_storage, Storage};
+use crate::tracking::splits::Split;
 
 
-#[derive(serde::Serialize, serde::Deserialize)]
+const BG_COLOR: (f32, f32, f32) = (0.6, 0.8, 0.2);
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Record {
-    timestamp: f64,
-    distance: f64,
-    time: f64,
-    speed: f64,
+    pub id: u64,
+    pub timestamp: f64,
+    pub distance: f64,
+    /// moving time
+    pub time: f64,
+    /// time from start to finish, pauses included
+    #[serde(default)]
+    pub elapsed_time: f64,
+    pub speed: f64,
+    /// number of points in the track file, the track itself is loaded on demand
+    pub track_points: usize,
+    #[serde(default)]
+    pub splits: Vec<Split>,
+    #[serde(default)]
+    pub laps: Vec<Split>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -35,16 +55,45 @@ pub struct Records {
     pub avg_speed: f64,
 }
 
-pub fn push_new_record(gps_data: &MutexGuard<GpsData>) {
-    let mut records = RECORDS_LIST.lock().unwrap();
+impl Default for Records {
+    fn default() -> Self {
+        Records {
+            records: vec![],
+            total_distance: 0.0,
+            total_time: 0.0,
+            avg_speed: 0.0,
+        }
+    }
+}
 
+pub fn push_new_record(gps_data: &MutexGuard<GpsData>) {
     //UNIX EPOCH
     let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+    push_record(gps_data, now);
+}
+
+/// Saves finished training (live or imported) finished at `timestamp`, returns id of the new record
+pub fn push_record(gps_data: &GpsData, timestamp: f64) -> u64 {
+    let mut records = RECORDS_LIST.lock().unwrap();
+    add_record(&mut records, get_storage(), gps_data, timestamp)
+}
+
+/// Adds the training to `records` and saves both into `storage`, returns id of the new record
+pub(crate) fn add_record(records: &mut Records, storage: Option<&Storage>, gps_data: &GpsData, timestamp: f64) -> u64 {
+    // track files of records that failed to load are still there
+    let last_track_id = storage.map(|storage| storage.last_track_id()).unwrap_or(0);
+    let id = records.records.iter().map(|r| r.id).max().unwrap_or(0).max(last_track_id) + 1;
+    let track = gps_data.track();
     let record = Record {
-        timestamp: now,
+        id,
+        timestamp,
         distance: gps_data.total_distance(),
-        time: gps_data.total_time(),
+        time: gps_data.moving_time(),
+        elapsed_time: gps_data.elapsed_time(),
         speed: gps_data.avg_speed(),
+        track_points: track.len(),
+        splits: gps_data.splits().final_splits(),
+        laps: gps_data.splits().final_laps(),
     };
 
     records.total_distance += record.distance;
@@ -56,23 +105,30 @@ pub fn push_new_record(gps_data: &MutexGuard<GpsData>) {
         records.avg_speed = records.total_distance / records.total_time;
     }
 
-    records.records.push(record);
+    // imported trainings may be older than the ones already stored
+    let pos = records.records.partition_point(|r| r.timestamp <= record.timestamp);
+    records.records.insert(pos, record);
+
+    if let Some(storage) = storage {
+        if let Err(err) = storage.save_track(id, track) {
+            error!("[Records] Failed to save track of record {}: {}", id, err);
+        }
+        if let Err(err) = storage.save_records(records) {
+            error!("[Records] Failed to save records: {}", err);
+        }
+    }
+    id
+}
 
-    // let records_path = format!("{}/records.json", ANDROID_DATA_PATH);
-    //
-    // //write test content
-    // let mut file = File::create(&records_path).unwrap();
-    // let records = RECORDS_LIST.lock().unwrap();
-    // file.write_all(serde_json::to_string(&*records).unwrap().as_bytes()).unwrap();
+impl Records {
+    /// Sorted by timestamp, oldest first
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
 }
 
 lazy_static!(
-    pub static ref RECORDS_LIST: Mutex<Records> = Mutex::new(Records {
-        records: vec![],
-        total_distance: 0.0,
-        total_time: 0.0,
-        avg_speed: 0.0,
-    });
+    pub static ref RECORDS_LIST: Mutex<Records> = Mutex::new(Records::default());
 );
 
 pub struct RecordsScreen {
@@ -83,102 +139,101 @@ pub struct RecordsScreen {
 
     logo: Image,
 
-    record_info: TextBox,
-    record_square: Squad,
-
-    bottom_home_text: TextBox,
-    bottom_records_text: TextBox,
-    bottom_stats_text: TextBox,
+    /// texts of the cards, built again when the list or the units change
+    rows: Vec<TextBox>,
+    rows_key: Option<(usize, UnitSystem)>,
+    /// card of a record, moved to every record in turn
+    record_card: Panel,
 
-    home_icon: Image,
-    records_icon: Image,
-    stats_icon: Image,
+    nav: NavBar,
 
-    exit_request: Arc<AtomicBool>,
     start: Instant,
 
     scroll_offset: f64,
 }
 
 impl RecordsScreen {
-    pub fn new(gl: Arc<gl::Gl>, exit_request: Arc<AtomicBool>) -> Self {
-        let squad = Squad::new_bg(gl.clone(), (0.6, 0.8, 0.2));
+    pub fn new(gl: Arc<gl::Gl>) -> Self {
+        let squad = Squad::new_bg(settings().theme.background(BG_COLOR));
 
         let dims = (SURFACE_WIDTH.load(Ordering::Relaxed), SURFACE_HEIGHT.load(Ordering::Relaxed));
 
         let circ_anim = CircleAnimation::new(1.0, [(0.5, 0.5, 0.5), (-0.5, -0.2, 0.0), (0.0, 2.0, 3.0)]);
         let screen_rendering = ScreenRendering::new(gl.clone(), dims, circ_anim);
 
-        let font = get_font("queensides").unwrap();
-
-        let logo = Image::new(gl.clone(), get_image("panther_logo").unwrap(),
+        let logo = Image::new(get_image("panther_logo").unwrap(),
                                                                          FixedPosition::new().bottom(1.75).width(0.25).left(0.65), Some((0.05, 0.06, 0.1)));
 
-        let bottom_home_text = TextBox::new(gl.clone(), font.clone(), "Home".to_string(), (0.2, 0.068), 0.45, 1);
-        let bottom_records_text = TextBox::new(gl.clone(), font.clone(), "Records".to_string(), (0.44, 0.068), 0.45, 1);
-        let bottom_stats_text = TextBox::new(gl.clone(), font.clone(), "Stats".to_string(), (0.72, 0.068), 0.45, 1);
-
-        let home_icon = Image::new(gl.clone(), get_image("home").unwrap(),
-                                   FixedPosition::new().bottom(0.12).height(0.08).left(0.2), Some((0.4, 0.2, 0.6)));
-        let records_icon = Image::new(gl.clone(), get_image("records").unwrap(),
-                                      FixedPosition::new().bottom(0.12).height(0.08).left(0.45), Some((1.0, 0.9, 1.0)));
-        let stats_icon = Image::new(gl.clone(), get_image("stats").unwrap(),
-                                    FixedPosition::new().bottom(0.12).height(0.08).left(0.715), Some((0.4, 0.5, 0.9)));
+        let nav = NavBar::new(gl.clone(), NavTab::Records);
 
-        let record_info = TextBox::new(gl.clone(), font.clone(), "Record 0".to_string(), (0.12, 1.5), 0.68, 1);
-        let record_square = Squad::new(gl.clone(), (0.5, 0.3, 0.5, 1.0),
-            FreePosition::new().bottom(1.38).left(0.1).width(0.8).height(0.2));
+        let record_card = Panel::new(Squad::new((0.5, 0.3, 0.5, 1.0),
+            FreePosition::new().bottom(1.38).left(0.1).width(0.8).height(0.2)));
 
         RecordsScreen {
             gl,
             bg_squad: squad,
 
-            exit_request,
-            start: Instant::now(),
+            start: clock::now(),
             screen_rendering,
 
             logo,
 
-            record_info,
-            record_square,
+            rows: vec![],
+            rows_key: None,
+            record_card,
 
-            bottom_home_text,
-            bottom_records_text,
-            bottom_stats_text,
-
-            home_icon,
-            records_icon,
-            stats_icon,
+            nav,
 
             scroll_offset: 0.0,
         }
     }
+
+    fn update_rows(&mut self, records: &Records) {
+        let units = settings().units;
+        let key = (records.records.len(), units);
+        if self.rows_key == Some(key) {
+            return;
+        }
+        self.rows_key = Some(key);
+
+        let font = get_font("queensides").unwrap();
+        self.rows = records.records.iter().enumerate().map(|(i, record)| {
+            let text = format!("Record {}\n{} {} in {}, {} /{}", i, format::distance(record.distance, units), units.long_label(),
+                               format::duration(record.time), format::speed_pace(record.speed, units), units.long_label());
+            // two lines fit on a card
+            TextBox::new(self.gl.clone(), font.clone(), text, (0.12, 1.5 - 0.3 * i as f32), 0.68, 1)
+                .layout(TextLayout::new().max_width(0.76).max_lines(2))
+        }).collect();
+    }
 }
 
 impl ScreenTrait for RecordsScreen {
     fn press(&mut self, pos: (f64, f64)) -> ScreenManagementCmd {
-        if pos.1 < 0.25 {
-            match pos.0 {
-                x if x < 0.33 => {
-                    ScreenManagementCmd::PushScreen(Box::new(MainScreen::new(self.gl.clone(), self.exit_request.clone())))
-                }
-                x if x < 0.66 => {
-                    // ScreenManagementCmd::PushScreen(Box::new(RecordsScreen::new(self.gl.clone(), self.exit_request.clone())))
-                    ScreenManagementCmd::None
-                }
-                _ => {
-                    ScreenManagementCmd::PushScreen(Box::new(StatsScreen::new(self.gl.clone(), self.exit_request.clone())))
-                }
-
-            }
+        if let Some(cmd) = self.nav.press(pos) {
+            return cmd;
+        }
+        // the bar is drawn over the cards scrolled under it
+        if contains(self.nav.bounds(), pos) {
+            return ScreenManagementCmd::None;
         }
-        else {
-            ScreenManagementCmd::None
+
+        let records = RECORDS_LIST.lock().unwrap();
+        let card = records.records.iter().enumerate().find(|(i, _)| {
+            self.record_card.set_y_offset(- 0.3 * *i as f64 + self.scroll_offset);
+            self.record_card.hit(pos)
+        });
+        match card {
+            Some((i, record)) => ScreenManagementCmd::PushScreen(Box::new(
+                RecordDetailScreen::new(self.gl.clone(), record.clone(), i))),
+            None => ScreenManagementCmd::None,
         }
     }
     fn back(&mut self) -> ScreenManagementCmd {
         // self.exit_request.store(true, Ordering::Relaxed);
-        ScreenManagementCmd::PushScreen(Box::new(MainScreen::new(self.gl.clone(), self.exit_request.clone())))
+        ScreenManagementCmd::SwitchTab(NavTab::Home)
+    }
+    fn resume(&mut self) {
+        self.bg_squad.set_color(settings().theme.background(BG_COLOR));
     }
     fn draw(&mut self) {
         let texture_id = self.screen_rendering.texture_id();
@@ -188,25 +243,16 @@ impl ScreenTrait for RecordsScreen {
 
         self.logo.draw(texture_id);
 
-        let records = RECORDS_LIST.lock().unwrap();
-        for (i, record) in records.records.iter().enumerate() {
-            let text = format!("Record {}\n{:.2}m in {:.2}s at {:.2}m/s", i, record.distance, record.time, record.speed);
-            self.record_square.set_pos_y_offset(- 0.3 * i as f64 + self.scroll_offset);
-
-            self.record_info.set_text(text);
-            self.record_info.set_pos((0.12, 1.5 - 0.3 * i as f32 + self.scroll_offset as f32));
+        self.update_rows(&RECORDS_LIST.lock().unwrap());
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            self.record_card.set_y_offset(- 0.3 * i as f64 + self.scroll_offset);
+            row.set_pos_y_offs(self.scroll_offset);
 
-            self.record_square.draw(texture_id);
-            self.record_info.draw(texture_id);
+            self.record_card.draw(texture_id);
+            row.draw(texture_id);
         }
 
-        self.bottom_home_text.draw(texture_id);
-        self.bottom_records_text.draw(texture_id);
-        self.bottom_stats_text.draw(texture_id);
-
-        self.home_icon.draw(texture_id);
-        self.records_icon.draw(texture_id);
-        self.stats_icon.draw(texture_id);
+        self.nav.draw(texture_id);
 
         self.screen_rendering.present();
     }
@@ -214,6 +260,6 @@ impl ScreenTrait for RecordsScreen {
         self.scroll_offset += pos.1;
     }
     fn is_expanded(&self) -> bool {
-        Instant::now().duration_since(self.start).as_secs_f32() > 1.0
+        clock::elapsed(self.start).as_secs_f32() > 1.0
     }
 }
\ No newline at end of file
@@ -0,0 +1,300 @@
+use std::sync::{Arc};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use crate::render::{gl, SURFACE_HEIGHT, SURFACE_WIDTH};
+use crate::render::fonts::get_font;
+use crate::render::images::get_image;
+use crate::render::objects::image::Image;
+use crate::render::objects::r#box::Squad;
+use crate::render::objects::textbox::TextBox;
+use crate::render::screens::{ScreenManagementCmd, ScreenRendering, ScreenTrait};
+use crate::render::utils::circle_animation::CircleAnimation;
+use crate::render::utils::position::{FixedPosition, FreePosition};
+use crate::render::utils::clock;
+use crate::render::widgets::{Bounds, Container, Widget, WidgetId};
+use crate::settings::{settings, update_settings, Settings};
+
+const BG_COLOR: (f32, f32, f32) = (0.4, 0.2, 0.6);
+const ON_COLOR: (f32, f32, f32) = (0.6, 0.8, 0.2);
+const OFF_COLOR: (f32, f32, f32) = (0.25, 0.15, 0.35);
+
+const FIRST_ROW: f64 = 1.5;
+const ROW_HEIGHT: f64 = 0.22;
+
+const SLIDER_LEFT: f64 = 0.07;
+const SLIDER_WIDTH: f64 = 0.86;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Setting {
+    Units,
+    AccuracyThreshold,
+    Warmup,
+    AutoPause,
+    Theme,
+}
+
+impl Setting {
+    const ALL: [Setting; 5] = [Setting::Units, Setting::AccuracyThreshold, Setting::Warmup, Setting::AutoPause, Setting::Theme];
+
+    /// (min, max, step) of a slider, None for a toggle
+    fn range(self) -> Option<(f64, f64, f64)> {
+        match self {
+            Setting::AccuracyThreshold => Some((2.0, 20.0, 0.5)),
+            Setting::Warmup => Some((0.0, 30.0, 1.0)),
+            _ => None,
+        }
+    }
+
+    fn value(self, settings: &Settings) -> f64 {
+        match self {
+            Setting::AccuracyThreshold => settings.accuracy_threshold,
+            Setting::Warmup => settings.warmup,
+            _ => 0.0,
+        }
+    }
+
+    fn is_on(self, settings: &Settings) -> bool {
+        match self {
+            Setting::Units => settings.units != Default::default(),
+            Setting::AutoPause => settings.auto_pause,
+            Setting::Theme => settings.theme != Default::default(),
+            _ => false,
+        }
+    }
+
+    fn text(self, settings: &Settings) -> String {
+        let units = settings.units;
+        match self {
+            Setting::Units => format!("Units: {}", units.name()),
+            Setting::AccuracyThreshold => format!("GPS accuracy: {:.1} {}",
+                                                  settings.accuracy_threshold / units.short_unit(), units.short_label()),
+            Setting::Warmup => format!("GPS warm-up: {:.0} s", settings.warmup),
+            Setting::AutoPause => format!("Auto-pause: {}", if settings.auto_pause { "on" } else { "off" }),
+            Setting::Theme => format!("Theme: {}", settings.theme.name()),
+        }
+    }
+
+    fn toggle(self, settings: &mut Settings) {
+        match self {
+            Setting::Units => settings.units = settings.units.toggled(),
+            Setting::AutoPause => settings.auto_pause = !settings.auto_pause,
+            Setting::Theme => settings.theme = settings.theme.toggled(),
+            _ => {}
+        }
+    }
+
+    fn set_value(self, settings: &mut Settings, value: f64) {
+        match self {
+            Setting::AccuracyThreshold => settings.accuracy_threshold = value,
+            Setting::Warmup => settings.warmup = value,
+            _ => {}
+        }
+    }
+}
+
+/// Label with a switch on the right, or with a slider under it
+struct SettingRow {
+    setting: Setting,
+    bottom: f64,
+    label: TextBox,
+    /// switch or slider track
+    track: Squad,
+    /// switch knob or filled part of the slider
+    fill: Squad,
+    y_offset: f64,
+}
+
+impl SettingRow {
+    fn new(gl: Arc<gl::Gl>, setting: Setting, bottom: f64) -> Self {
+        let font = get_font("queensides").unwrap();
+        let label = TextBox::new(gl.clone(), font, String::new(), (0.07, (bottom + 0.09) as f32), 0.6, 1);
+
+        let (track, fill) = if setting.range().is_some() {
+            (Squad::new((OFF_COLOR.0, OFF_COLOR.1, OFF_COLOR.2, 1.0),
+                        FreePosition::new().left(SLIDER_LEFT).bottom(bottom + 0.02).width(SLIDER_WIDTH).height(0.03)),
+             Squad::new((ON_COLOR.0, ON_COLOR.1, ON_COLOR.2, 1.0),
+                        FreePosition::new().left(SLIDER_LEFT).bottom(bottom + 0.02).width(0.0).height(0.03)))
+        }
+        else {
+            (Squad::new((OFF_COLOR.0, OFF_COLOR.1, OFF_COLOR.2, 1.0),
+                        FreePosition::new().left(0.75).bottom(bottom + 0.07).width(0.18).height(0.08)),
+             Squad::new((1.0, 0.9, 1.0, 1.0),
+                        FreePosition::new().left(0.76).bottom(bottom + 0.08).width(0.07).height(0.06)))
+        };
+
+        let mut row = SettingRow {
+            setting,
+            bottom,
+            label,
+            track,
+            fill,
+            y_offset: 0.0,
+        };
+        row.refresh(&settings());
+        row
+    }
+
+    /// Slider value under `x`, rounded to the step
+    fn value_at(&self, x: f64) -> Option<f64> {
+        let (min, max, step) = self.setting.range()?;
+        let fraction = ((x - SLIDER_LEFT) / SLIDER_WIDTH).clamp(0.0, 1.0);
+        Some(((min + (max - min) * fraction) / step).round() * step)
+    }
+
+    fn refresh(&mut self, settings: &Settings) {
+        self.label.set_text(self.setting.text(settings));
+
+        match self.setting.range() {
+            Some((min, max, _)) => {
+                let fraction = (self.setting.value(settings) - min) / (max - min);
+                self.fill.set_pos(FreePosition::new().left(SLIDER_LEFT).bottom(self.bottom + 0.02)
+                    .width(SLIDER_WIDTH * fraction.clamp(0.0, 1.0)).height(0.03));
+            }
+            None => {
+                let on = self.setting.is_on(settings);
+                self.track.set_color(if on { ON_COLOR } else { OFF_COLOR });
+                self.fill.set_pos(FreePosition::new().left(if on { 0.85 } else { 0.76 }).bottom(self.bottom + 0.08)
+                    .width(0.07).height(0.06));
+            }
+        }
+    }
+
+}
+
+impl Widget for SettingRow {
+    /// The whole row, sliders are easier to catch this way
+    fn bounds(&self) -> Bounds {
+        (0.0, self.bottom + self.y_offset, 1.0, ROW_HEIGHT)
+    }
+    fn set_y_offset(&mut self, offset: f64) {
+        self.y_offset = offset;
+        self.label.set_pos_y_offs(offset);
+        self.track.set_pos_y_offset(offset);
+        self.fill.set_pos_y_offset(offset);
+    }
+    fn draw(&mut self, texture_id: u32) {
+        self.track.draw(texture_id);
+        self.fill.draw(texture_id);
+        self.label.draw(texture_id);
+    }
+}
+
+/// Preferences, saved on every change. Trainings started afterwards use them.
+pub struct SettingsScreen {
+    bg_squad: Squad,
+    screen_rendering: ScreenRendering,
+
+    start: Instant,
+
+    logo: Image,
+    title: TextBox,
+
+    rows: Container<Setting>,
+    /// in the order of `Setting::ALL`
+    row_ids: Vec<WidgetId<SettingRow>>,
+    /// slider being dragged and the x of the touch
+    dragging: Option<(Setting, f64)>,
+}
+
+impl SettingsScreen {
+    pub fn new(gl: Arc<gl::Gl>) -> Self {
+        let squad = Squad::new_bg(settings().theme.background(BG_COLOR));
+
+        let dims = (SURFACE_WIDTH.load(Ordering::Relaxed), SURFACE_HEIGHT.load(Ordering::Relaxed));
+
+        let circ_anim = CircleAnimation::new(1.0, [(0.5, 0.5, 0.5), (-0.5, -0.2, 0.0), (0.0, 2.0, 3.0)]);
+        let screen_rendering = ScreenRendering::new(gl.clone(), dims, circ_anim);
+
+        let font = get_font("queensides").unwrap();
+
+        let logo = Image::new(get_image("panther_logo").unwrap(),
+                              FixedPosition::new().bottom(1.75).width(0.25).left(0.65), Some((1.0, 0.9, 1.0)));
+
+        let title = TextBox::new(gl.clone(), font, "Settings".to_string(), (0.07, 1.8), 1.0, 1);
+
+        let mut rows = Container::new();
+        let row_ids = Setting::ALL.iter().enumerate()
+            .map(|(i, setting)| rows.add_tappable(SettingRow::new(gl.clone(), *setting, FIRST_ROW - ROW_HEIGHT * i as f64), *setting))
+            .collect();
+
+        SettingsScreen {
+            bg_squad: squad,
+            screen_rendering,
+
+            start: clock::now(),
+
+            logo,
+            title,
+
+            rows,
+            row_ids,
+            dragging: None,
+        }
+    }
+
+    fn row(&mut self, setting: Setting) -> &mut SettingRow {
+        let i = Setting::ALL.iter().position(|s| *s == setting).unwrap();
+        self.rows.get_mut(self.row_ids[i])
+    }
+
+    fn change(&mut self, f: impl FnOnce(&mut Settings)) {
+        update_settings(f);
+
+        let settings = settings();
+        for id in self.row_ids.iter() {
+            self.rows.get_mut(*id).refresh(&settings);
+        }
+        self.bg_squad.set_color(settings.theme.background(BG_COLOR));
+    }
+}
+
+impl ScreenTrait for SettingsScreen {
+    fn start_scroll(&mut self, pos: (f64, f64)) -> bool {
+        self.dragging = self.rows.press(pos)
+            .filter(|setting| setting.range().is_some())
+            .map(|setting| (setting, pos.0));
+        self.dragging.is_some()
+    }
+    fn scroll(&mut self, pos: (f64, f64)) {
+        let Some((setting, x)) = self.dragging else {
+            return;
+        };
+        let x = x + pos.0;
+        self.dragging = Some((setting, x));
+
+        if let Some(value) = self.row(setting).value_at(x) {
+            if value != setting.value(&settings()) {
+                self.change(|s| setting.set_value(s, value));
+            }
+        }
+    }
+    fn press(&mut self, pos: (f64, f64)) -> ScreenManagementCmd {
+        let Some(setting) = self.rows.press(pos) else {
+            return ScreenManagementCmd::None;
+        };
+        match self.row(setting).value_at(pos.0) {
+            Some(value) => self.change(|s| setting.set_value(s, value)),
+            None => self.change(|s| setting.toggle(s)),
+        }
+        ScreenManagementCmd::None
+    }
+    fn back(&mut self) -> ScreenManagementCmd {
+        ScreenManagementCmd::PopScreen
+    }
+    fn draw(&mut self) {
+        let texture_id = self.screen_rendering.texture_id();
+        self.screen_rendering.clear_texture();
+
+        self.bg_squad.draw(texture_id);
+
+        self.logo.draw(texture_id);
+        self.title.draw(texture_id);
+
+        self.rows.draw(texture_id);
+
+        self.screen_rendering.present();
+    }
+    fn is_expanded(&self) -> bool {
+        clock::elapsed(self.start).as_secs_f32() > 1.0
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use crate::render::gl;
+use crate::render::gl::types::{GLenum, GLint, GLuint};
+
+/// GLSL file of the crate, embedded into the binary. Path is relative to the crate root,
+/// the desktop build reloads the file from there when it changes
+#[derive(Clone, Copy, Debug)]
+pub struct ShaderSource {
+    pub path: &'static str,
+    pub source: &'static [u8],
+}
+
+/// `ShaderSource` of a GLSL file, path relative to the crate root
+#[macro_export]
+macro_rules! shader_source {
+    ($path:literal) => {
+        $crate::render::shader::ShaderSource {
+            path: $path,
+            source: include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)),
+        }
+    };
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Compile { path: &'static str, log: String },
+    Link { key: &'static str, log: String },
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderError::Compile { path, log } => write!(f, "failed to compile {}:\n{}", path, log),
+            ShaderError::Link { key, log } => write!(f, "failed to link program {}:\n{}", key, log),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+struct CachedShader {
+    id: GLuint,
+    #[cfg(feature = "desktop")]
+    modified: Option<std::time::SystemTime>,
+}
+
+struct CachedProgram {
+    id: GLuint,
+    #[cfg(feature = "desktop")]
+    vertex: ShaderSource,
+    #[cfg(feature = "desktop")]
+    fragment: ShaderSource,
+}
+
+/// Shaders compiled once per source and programs linked once per key.
+/// Shader objects stay alive, so programs sharing a source reuse them
+pub struct ShaderCache {
+    gl: Arc<gl::Gl>,
+    shaders: HashMap<&'static str, CachedShader>,
+    programs: HashMap<&'static str, CachedProgram>,
+    #[cfg(feature = "desktop")]
+    last_reload: std::time::Instant,
+}
+
+impl ShaderCache {
+    pub fn new(gl: Arc<gl::Gl>) -> Self {
+        ShaderCache {
+            gl,
+            shaders: HashMap::new(),
+            programs: HashMap::new(),
+            #[cfg(feature = "desktop")]
+            last_reload: std::time::Instant::now(),
+        }
+    }
+
+    /// Program of the key, linked on the first call with the attributes bound to their indices
+    pub fn program(&mut self, key: &'static str, vertex: ShaderSource, fragment: ShaderSource, attributes: &[&CStr]) -> Result<GLuint, ShaderError> {
+        if let Some(program) = self.programs.get(key) {
+            return Ok(program.id);
+        }
+
+        let vertex_shader = self.shader(gl::VERTEX_SHADER, vertex)?;
+        let fragment_shader = self.shader(gl::FRAGMENT_SHADER, fragment)?;
+
+        let gl = &self.gl;
+        let id = unsafe {
+            let id = gl.CreateProgram();
+            gl.AttachShader(id, vertex_shader);
+            gl.AttachShader(id, fragment_shader);
+            for (index, name) in attributes.iter().enumerate() {
+                gl.BindAttribLocation(id, index as GLuint, name.as_ptr());
+            }
+            id
+        };
+        if let Err(err) = link(gl, key, id) {
+            unsafe { gl.DeleteProgram(id) };
+            return Err(err);
+        }
+
+        self.programs.insert(key, CachedProgram {
+            id,
+            #[cfg(feature = "desktop")]
+            vertex,
+            #[cfg(feature = "desktop")]
+            fragment,
+        });
+        Ok(id)
+    }
+
+    fn shader(&mut self, kind: GLenum, source: ShaderSource) -> Result<GLuint, ShaderError> {
+        if let Some(shader) = self.shaders.get(source.path) {
+            return Ok(shader.id);
+        }
+
+        // the file on disk can be newer than the binary
+        #[cfg(feature = "desktop")]
+        let file = read_source(source.path);
+        #[cfg(feature = "desktop")]
+        let (text, modified) = match &file {
+            Some((text, modified)) => (text.as_slice(), Some(*modified)),
+            None => (source.source, None),
+        };
+        #[cfg(not(feature = "desktop"))]
+        let text = source.source;
+
+        let id = compile(&self.gl, kind, source.path, text)?;
+        self.shaders.insert(source.path, CachedShader {
+            id,
+            #[cfg(feature = "desktop")]
+            modified,
+        });
+        Ok(id)
+    }
+
+    /// Compiles the shader files changed on disk since the last call, at most twice a second,
+    /// and relinks their programs. Returns the keys of the relinked programs.
+    ///
+    /// Programs keep their names, but uniforms are reset and can move,
+    /// so owners of the returned keys set them up again. Errors are logged,
+    /// a shader that fails to compile or to link keeps the previous version.
+    #[cfg(feature = "desktop")]
+    pub fn reload(&mut self) -> Vec<&'static str> {
+        const RELOAD_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+        if self.last_reload.elapsed() < RELOAD_INTERVAL {
+            return vec![];
+        }
+        self.last_reload = std::time::Instant::now();
+
+        let sources: Vec<(GLenum, ShaderSource)> = self.programs.values()
+            .flat_map(|program| [(gl::VERTEX_SHADER, program.vertex), (gl::FRAGMENT_SHADER, program.fragment)])
+            .collect();
+
+        let gl = &self.gl;
+        let mut relinked = vec![];
+        for (kind, source) in sources {
+            let shader = self.shaders.get_mut(source.path).unwrap();
+            let Some((text, modified)) = read_source(source.path) else {
+                continue;
+            };
+            if shader.modified == Some(modified) {
+                continue;
+            }
+            shader.modified = Some(modified);
+
+            let id = match compile(gl, kind, source.path, &text) {
+                Ok(id) => id,
+                Err(err) => {
+                    log::error!("[Shaders] {}", err);
+                    continue;
+                }
+            };
+
+            let programs: Vec<(&'static str, GLuint)> = self.programs.iter()
+                .filter(|(_, program)| program.vertex.path == source.path || program.fragment.path == source.path)
+                .map(|(key, program)| (*key, program.id))
+                .collect();
+            // swapped back or not, the programs are linked again
+            for (key, _) in &programs {
+                if !relinked.contains(key) {
+                    relinked.push(*key);
+                }
+            }
+
+            match swap_shader(gl, &programs, shader.id, id) {
+                Ok(()) => {
+                    log::info!("[Shaders] Reloaded {}", source.path);
+                    unsafe { gl.DeleteShader(shader.id) };
+                    shader.id = id;
+                }
+                Err(err) => {
+                    log::error!("[Shaders] {}", err);
+                    unsafe { gl.DeleteShader(id) };
+                }
+            }
+        }
+        relinked
+    }
+}
+
+/// Attaches `new` instead of `old` to the programs and links them. If a program fails to link,
+/// the ones swapped so far get `old` back, so they stay usable
+#[cfg(feature = "desktop")]
+fn swap_shader(gl: &gl::Gl, programs: &[(&'static str, GLuint)], old: GLuint, new: GLuint) -> Result<(), ShaderError> {
+    for (i, (key, program)) in programs.iter().enumerate() {
+        unsafe {
+            gl.DetachShader(*program, old);
+            gl.AttachShader(*program, new);
+        }
+        if let Err(err) = link(gl, key, *program) {
+            for (key, program) in &programs[..=i] {
+                unsafe {
+                    gl.DetachShader(*program, new);
+                    gl.AttachShader(*program, old);
+                }
+                if let Err(err) = link(gl, key, *program) {
+                    log::error!("[Shaders] {}", err);
+                }
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Source of the shader file and its modification time, None without the source tree
+#[cfg(feature = "desktop")]
+fn read_source(path: &str) -> Option<(Vec<u8>, std::time::SystemTime)> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+    let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+    let text = std::fs::read(&path).ok()?;
+    Some((text, modified))
+}
+
+fn compile(gl: &gl::Gl, kind: GLenum, path: &'static str, source: &[u8]) -> Result<GLuint, ShaderError> {
+    unsafe {
+        let shader = gl.CreateShader(kind);
+        let len = source.len() as GLint;
+        gl.ShaderSource(shader, 1, [source.as_ptr().cast()].as_ptr(), &len);
+        gl.CompileShader(shader);
+
+        let mut status = 0;
+        gl.GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+        if status == 0 {
+            let mut len = 0;
+            gl.GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+            let mut log = vec![0u8; len.max(1) as usize];
+            gl.GetShaderInfoLog(shader, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+            gl.DeleteShader(shader);
+            return Err(ShaderError::Compile { path, log: info_log(log) });
+        }
+        Ok(shader)
+    }
+}
+
+fn link(gl: &gl::Gl, key: &'static str, program: GLuint) -> Result<(), ShaderError> {
+    unsafe {
+        gl.LinkProgram(program);
+
+        let mut status = 0;
+        gl.GetProgramiv(program, gl::LINK_STATUS, &mut status);
+        if status == 0 {
+            let mut len = 0;
+            gl.GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+            let mut log = vec![0u8; len.max(1) as usize];
+            gl.GetProgramInfoLog(program, len, std::ptr::null_mut(), log.as_mut_ptr().cast());
+            return Err(ShaderError::Link { key, log: info_log(log) });
+        }
+        Ok(())
+    }
+}
+
+fn info_log(mut log: Vec<u8>) -> String {
+    log.truncate(log.iter().position(|c| *c == 0).unwrap_or(log.len()));
+    String::from_utf8_lossy(&log).trim_end().to_string()
+}
@@ -1,5 +1,6 @@
 use std::time::Instant;
 use crate::render::gl::types::GLfloat;
+use crate::render::utils::clock;
 
 pub struct CircleAnimation {
     coefs: [(f64, f64, f64); 3],
@@ -27,16 +28,16 @@ impl CircleAnimation {
         CircleAnimation {
             coefs,
             duration,
-            start: Instant::now(),
+            start: clock::now(),
         }
     }
 
     pub fn start(&mut self) {
-        self.start = Instant::now();
+        self.start = clock::now();
     }
 
     pub fn cur(&self) -> (GLfloat, GLfloat, GLfloat) {
-        let elapsed_s = (Instant::now() - self.start).as_secs_f64();
+        let elapsed_s = (clock::now() - self.start).as_secs_f64();
         let t = (elapsed_s / self.duration).min(1.0);
 
         let c_x = self.coefs[0].0 * t*t + self.coefs[0].1 * t + self.coefs[0].2;
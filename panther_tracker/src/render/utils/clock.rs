@@ -0,0 +1,28 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Time seen by UI animations. Normally the real time, headless rendering freezes it
+/// and moves it forward by a fixed step per frame, so rendered frames are reproducible.
+static MANUAL_TIME: Mutex<Option<Instant>> = Mutex::new(None);
+
+pub fn now() -> Instant {
+    MANUAL_TIME.lock().unwrap().unwrap_or_else(Instant::now)
+}
+
+pub fn elapsed(since: Instant) -> Duration {
+    now().saturating_duration_since(since)
+}
+
+/// Freezes the clock at the current moment, it moves only with `advance` from now on
+pub fn freeze() {
+    let mut time = MANUAL_TIME.lock().unwrap();
+    if time.is_none() {
+        *time = Some(Instant::now());
+    }
+}
+
+pub fn advance(step: Duration) {
+    if let Some(time) = MANUAL_TIME.lock().unwrap().as_mut() {
+        *time += step;
+    }
+}
@@ -1,2 +1,5 @@
 pub mod circle_animation;
-pub mod position;
\ No newline at end of file
+pub mod clock;
+pub mod position;
+pub mod sdf;
+pub mod text_layout;
\ No newline at end of file
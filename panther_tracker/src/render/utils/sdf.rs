@@ -0,0 +1,130 @@
+/// Signed distance field of a glyph coverage bitmap, `oversample` times smaller than the bitmap
+/// and `spread` texels wider on every side. Bitmap sizes must be multiples of `oversample`.
+/// 128 is the edge, every `128 / spread` steps away from it is a texel in or out.
+pub fn distance_field(coverage: &[f32], width: usize, height: usize, spread: usize, oversample: usize) -> Vec<u8> {
+    let fine = fine_distance_field(coverage, width, height, spread * oversample);
+
+    // averaging keeps the edges where they are, hairlines survive the thresholds of the fine field
+    let fine_width = width + spread * oversample * 2;
+    let (field_width, field_height) = (width / oversample + spread * 2, height / oversample + spread * 2);
+    let mut field = Vec::with_capacity(field_width * field_height);
+    for y in 0..field_height {
+        for x in 0..field_width {
+            let mut sum = 0.0;
+            for fy in y * oversample..(y + 1) * oversample {
+                for fx in x * oversample..(x + 1) * oversample {
+                    sum += fine[fy * fine_width + fx];
+                }
+            }
+            let value = sum / (oversample * oversample) as f32;
+            field.push((value.clamp(0.0, 1.0) * 255.0) as u8);
+        }
+    }
+    field
+}
+
+/// Distance field of the same resolution as the bitmap, 0.5 on the edge
+fn fine_distance_field(coverage: &[f32], width: usize, height: usize, spread: usize) -> Vec<f32> {
+    let (field_width, field_height) = (width + spread * 2, height + spread * 2);
+
+    let coverage_at = |x: usize, y: usize| {
+        if x < spread || y < spread || x >= width + spread || y >= height + spread {
+            0.0
+        }
+        else {
+            coverage[(y - spread) * width + x - spread]
+        }
+    };
+
+    // squared distances to the nearest texel on the other side of the edge
+    let mut to_inside = vec![0.0; field_width * field_height];
+    let mut to_outside = vec![0.0; field_width * field_height];
+    for y in 0..field_height {
+        for x in 0..field_width {
+            let inside = coverage_at(x, y) > 0.5;
+            to_inside[y * field_width + x] = if inside { 0.0 } else { INF };
+            to_outside[y * field_width + x] = if inside { INF } else { 0.0 };
+        }
+    }
+    distance_transform(&mut to_inside, field_width, field_height);
+    distance_transform(&mut to_outside, field_width, field_height);
+
+    let mut field = Vec::with_capacity(field_width * field_height);
+    for y in 0..field_height {
+        for x in 0..field_width {
+            let idx = y * field_width + x;
+            let c = coverage_at(x, y);
+            // the edge lies between the texels of the two sides
+            let mut distance = if c > 0.5 {
+                to_outside[idx].sqrt() - 0.5
+            } else {
+                0.5 - to_inside[idx].sqrt()
+            };
+            // antialiased texels know where the edge crosses them better,
+            // and keep strokes thinner than a texel
+            if c > 0.01 && c < 0.99 {
+                distance = c - 0.5;
+            }
+            field.push(0.5 + distance / (spread as f32 * 2.0));
+        }
+    }
+    field
+}
+
+const INF: f32 = 1e20;
+
+/// Squared euclidean distance transform (Felzenszwalb & Huttenlocher), in place
+fn distance_transform(grid: &mut [f32], width: usize, height: usize) {
+    let len = width.max(height);
+    let mut f = vec![0.0; len];
+    let mut d = vec![0.0; len];
+    let mut v = vec![0; len];
+    let mut z = vec![0.0; len + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            f[y] = grid[y * width + x];
+        }
+        distance_transform_1d(&f[..height], &mut d, &mut v, &mut z);
+        for y in 0..height {
+            grid[y * width + x] = d[y];
+        }
+    }
+    for y in 0..height {
+        f[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        distance_transform_1d(&f[..width], &mut d, &mut v, &mut z);
+        grid[y * width..(y + 1) * width].copy_from_slice(&d[..width]);
+    }
+}
+
+/// Lower envelope of the parabolas rooted at `f`
+fn distance_transform_1d(f: &[f32], d: &mut [f32], v: &mut [usize], z: &mut [f32]) {
+    let intersection = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * q as f32 - 2.0 * p as f32)
+    };
+
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = -INF;
+    z[1] = INF;
+    for q in 1..f.len() {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = INF;
+    }
+
+    k = 0;
+    for (q, d) in d.iter_mut().enumerate().take(f.len()) {
+        while z[k + 1] < q as f32 {
+            k += 1;
+        }
+        let dq = q as f32 - v[k] as f32;
+        *d = dq * dq + f[v[k]];
+    }
+}
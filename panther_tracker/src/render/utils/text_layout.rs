@@ -0,0 +1,149 @@
+use crate::render::fonts::FontData;
+
+/// Ends lines that didn't fit
+const ELLIPSIS: &str = "...";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How a text is placed around its position
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextLayout {
+    align: Align,
+    max_width: Option<f32>,
+    max_lines: Option<usize>,
+}
+
+impl TextLayout {
+    pub fn new() -> TextLayout {
+        TextLayout::default()
+    }
+    /// Which end of the lines is at the x of the position
+    pub fn align(mut self, align: Align) -> TextLayout {
+        self.align = align;
+        self
+    }
+    /// Lines are wrapped between words to fit, words longer than a line are cut with an ellipsis
+    pub fn max_width(mut self, width: f32) -> TextLayout {
+        self.max_width = Some(width);
+        self
+    }
+    /// Lines past it are dropped, the last one shown ends with an ellipsis. At least one line is kept
+    pub fn max_lines(mut self, lines: usize) -> TextLayout {
+        self.max_lines = Some(lines.max(1));
+        self
+    }
+}
+
+/// Line of a laid out text, sizes are scaled
+#[derive(Clone, Debug)]
+pub struct Line {
+    pub text: String,
+    /// from the x of the position
+    pub left: f32,
+    pub width: f32,
+}
+
+/// Cursor advance of a character at scale 1, spaces are as wide as 'n'
+pub fn advance(font: &FontData, c: char) -> f32 {
+    if c == ' ' {
+        font.advance('n')
+    }
+    else {
+        font.advance(c)
+    }
+}
+
+/// Width of a line at scale 1: advances of its characters and kerning between them
+pub fn measure(font: &FontData, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut prev_char = None;
+    for c in text.chars() {
+        if c == ' ' {
+            prev_char = None;
+        }
+        else if let Some(p) = prev_char.replace(c) {
+            width += font.kern_space(p, c);
+        }
+        width += advance(font, c);
+    }
+    width
+}
+
+/// Breaks `text` into lines at '\n' and where the layout needs it
+pub fn layout(font: &FontData, text: &str, scale: f32, layout: &TextLayout) -> Vec<Line> {
+    let max_width = layout.max_width.map(|width| width / scale);
+
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        match max_width {
+            Some(max_width) => wrap(font, paragraph, max_width, &mut lines),
+            None => lines.push(paragraph.to_string()),
+        }
+    }
+
+    if let Some(max_lines) = layout.max_lines {
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if let Some(last) = lines.pop() {
+                lines.push(ellipsize(font, &last, max_width));
+            }
+        }
+    }
+
+    lines.into_iter().map(|text| {
+        let width = measure(font, &text) * scale;
+        let left = match layout.align {
+            Align::Left => 0.0,
+            Align::Center => -width / 2.0,
+            Align::Right => -width,
+        };
+        Line {
+            text,
+            left,
+            width,
+        }
+    }).collect()
+}
+
+fn wrap(font: &FontData, paragraph: &str, max_width: f32, lines: &mut Vec<String>) {
+    let mut line = String::new();
+    for word in paragraph.split(' ') {
+        if line.is_empty() {
+            line.push_str(word);
+        }
+        else {
+            let candidate = format!("{} {}", line, word);
+            if measure(font, &candidate) <= max_width {
+                line = candidate;
+            }
+            else {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            }
+        }
+
+        // a single word longer than the line
+        if measure(font, &line) > max_width {
+            line = ellipsize(font, &line, Some(max_width));
+        }
+    }
+    lines.push(line);
+}
+
+/// `text` with an ellipsis, shortened to fit `max_width`
+fn ellipsize(font: &FontData, text: &str, max_width: Option<f32>) -> String {
+    let mut text = text.trim_end().to_string();
+    loop {
+        let candidate = format!("{}{}", text, ELLIPSIS);
+        if text.is_empty() || max_width.is_none_or(|max_width| measure(font, &candidate) <= max_width) {
+            return candidate;
+        }
+        text.pop();
+        text.truncate(text.trim_end().len());
+    }
+}
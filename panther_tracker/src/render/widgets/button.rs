@@ -0,0 +1,40 @@
+use crate::render::objects::r#box::Squad;
+use crate::render::objects::textbox::TextBox;
+use crate::render::widgets::{Bounds, Widget};
+
+/// Text on a colored rectangle, tappable over the whole rectangle
+pub struct Button {
+    bg: Squad,
+    text: TextBox,
+}
+
+impl Button {
+    pub fn new(bg: Squad, text: TextBox) -> Self {
+        Button {
+            bg,
+            text,
+        }
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.text.set_text(text);
+    }
+
+    pub fn set_color(&mut self, color: (f32, f32, f32)) {
+        self.bg.set_color(color);
+    }
+}
+
+impl Widget for Button {
+    fn bounds(&self) -> Bounds {
+        self.bg.bounds()
+    }
+    fn set_y_offset(&mut self, offset: f64) {
+        self.bg.set_pos_y_offset(offset);
+        self.text.set_pos_y_offs(offset);
+    }
+    fn draw(&mut self, texture_id: u32) {
+        self.bg.draw(texture_id);
+        self.text.draw(texture_id);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::render::objects::image::Image;
+use crate::render::objects::textbox::TextBox;
+use crate::render::utils::position::FreePosition;
+use crate::render::widgets::{Bounds, Widget};
+
+/// Icon with an optional caption under it.
+/// Icons are small, so the tap area is given separately and usually covers more than the icon.
+pub struct IconButton {
+    icon: Image,
+    caption: Option<TextBox>,
+    area: Bounds,
+    y_offset: f64,
+}
+
+impl IconButton {
+    pub fn new(icon: Image, area: FreePosition) -> Self {
+        IconButton {
+            icon,
+            caption: None,
+            area: area.get(),
+            y_offset: 0.0,
+        }
+    }
+
+    pub fn caption(mut self, caption: TextBox) -> Self {
+        self.caption = Some(caption);
+        self
+    }
+}
+
+impl Widget for IconButton {
+    fn bounds(&self) -> Bounds {
+        (self.area.0, self.area.1 + self.y_offset, self.area.2, self.area.3)
+    }
+    fn set_y_offset(&mut self, offset: f64) {
+        self.y_offset = offset;
+        self.icon.set_pos_y_offset(offset);
+        if let Some(caption) = self.caption.as_mut() {
+            caption.set_pos_y_offs(offset);
+        }
+    }
+    fn draw(&mut self, texture_id: u32) {
+        if let Some(caption) = self.caption.as_mut() {
+            caption.draw(texture_id);
+        }
+        self.icon.draw(texture_id);
+    }
+}
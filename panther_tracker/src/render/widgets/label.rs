@@ -0,0 +1,46 @@
+use crate::render::objects::textbox::TextBox;
+use crate::render::widgets::{Bounds, Widget};
+
+/// Text, tappable around its glyphs
+pub struct Label {
+    text: TextBox,
+    /// added on every side of the glyphs, small texts are hard to hit
+    padding: f64,
+}
+
+impl Label {
+    pub fn new(text: TextBox) -> Self {
+        Label {
+            text,
+            padding: 0.0,
+        }
+    }
+
+    pub fn padding(mut self, padding: f64) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.text.set_text(text);
+    }
+
+    pub fn set_pos(&mut self, pos: (f32, f32)) {
+        self.text.set_pos(pos);
+    }
+}
+
+impl Widget for Label {
+    fn bounds(&self) -> Bounds {
+        let Some((left, bottom, width, height)) = self.text.bounds() else {
+            return (0.0, 0.0, 0.0, 0.0);
+        };
+        (left - self.padding, bottom - self.padding, width + self.padding * 2.0, height + self.padding * 2.0)
+    }
+    fn set_y_offset(&mut self, offset: f64) {
+        self.text.set_pos_y_offs(offset);
+    }
+    fn draw(&mut self, texture_id: u32) {
+        self.text.draw(texture_id);
+    }
+}
@@ -0,0 +1,128 @@
+// Tappable building blocks of the screens. A widget owns the bounds it is drawn at,
+// so screens ask a `Container` what was tapped instead of repeating the coordinates.
+
+use std::any::Any;
+use std::marker::PhantomData;
+use crate::render::objects::animated_image::AnimatedImage;
+
+pub mod button;
+pub mod icon_button;
+pub mod label;
+pub mod nav_bar;
+pub mod panel;
+
+/// (left, bottom, width, height) in the same units as positions of the objects
+pub type Bounds = (f64, f64, f64, f64);
+
+pub fn contains(bounds: Bounds, pos: (f64, f64)) -> bool {
+    let (left, bottom, width, height) = bounds;
+    pos.0 > left && pos.0 < left + width && pos.1 > bottom && pos.1 < bottom + height
+}
+
+pub trait Widget: Any {
+    /// Area that reacts to taps
+    fn bounds(&self) -> Bounds;
+    /// Moves the widget vertically from where it was created, used by scrolling and animations
+    fn set_y_offset(&mut self, offset: f64);
+    fn draw(&mut self, texture_id: u32);
+
+    fn hit(&self, pos: (f64, f64)) -> bool {
+        contains(self.bounds(), pos)
+    }
+}
+
+impl Widget for AnimatedImage {
+    fn bounds(&self) -> Bounds {
+        AnimatedImage::bounds(self)
+    }
+    fn set_y_offset(&mut self, offset: f64) {
+        self.set_pos_y_offset(offset);
+    }
+    fn draw(&mut self, texture_id: u32) {
+        AnimatedImage::draw(self, texture_id);
+    }
+}
+
+/// Handle of a widget added to a `Container`, remembers its type
+pub struct WidgetId<W> {
+    index: usize,
+    widget: PhantomData<fn() -> W>,
+}
+
+impl<W> Clone for WidgetId<W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W> Copy for WidgetId<W> {}
+
+struct Entry<A> {
+    widget: Box<dyn Widget>,
+    /// None for decorations, they are drawn but let taps through
+    action: Option<A>,
+    visible: bool,
+}
+
+/// Widgets of a screen part, drawn in the order they were added.
+/// A tap goes to the topmost visible widget with an action under it.
+pub struct Container<A> {
+    entries: Vec<Entry<A>>,
+}
+
+impl<A: Copy> Default for Container<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Copy> Container<A> {
+    pub fn new() -> Self {
+        Container {
+            entries: vec![],
+        }
+    }
+
+    pub fn add<W: Widget>(&mut self, widget: W) -> WidgetId<W> {
+        self.push(Box::new(widget), None)
+    }
+
+    pub fn add_tappable<W: Widget>(&mut self, widget: W, action: A) -> WidgetId<W> {
+        self.push(Box::new(widget), Some(action))
+    }
+
+    fn push<W>(&mut self, widget: Box<dyn Widget>, action: Option<A>) -> WidgetId<W> {
+        self.entries.push(Entry {
+            widget,
+            action,
+            visible: true,
+        });
+        WidgetId {
+            index: self.entries.len() - 1,
+            widget: PhantomData,
+        }
+    }
+
+    pub fn get_mut<W: Widget>(&mut self, id: WidgetId<W>) -> &mut W {
+        let widget: &mut dyn Any = self.entries[id.index].widget.as_mut();
+        widget.downcast_mut().unwrap()
+    }
+
+    /// Hidden widgets are neither drawn nor tapped
+    pub fn set_visible<W>(&mut self, id: WidgetId<W>, visible: bool) {
+        self.entries[id.index].visible = visible;
+    }
+
+    /// Action of the topmost widget under `pos`
+    pub fn press(&self, pos: (f64, f64)) -> Option<A> {
+        self.entries.iter().rev()
+            .filter(|entry| entry.visible)
+            .find_map(|entry| entry.action.filter(|_| entry.widget.hit(pos)))
+    }
+
+    pub fn draw(&mut self, texture_id: u32) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.visible) {
+            entry.widget.draw(texture_id);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+use std::time::Instant;
+use crate::render::gl;
+use crate::render::fonts::get_font;
+use crate::render::images::get_image;
+use crate::render::objects::image::Image;
+use crate::render::objects::textbox::TextBox;
+use crate::render::screens::ScreenManagementCmd;
+use crate::render::utils::clock;
+use crate::render::utils::position::{FixedPosition, FreePosition};
+use crate::render::utils::text_layout::{Align, TextLayout};
+use crate::render::widgets::{Bounds, Container, Widget, WidgetId};
+use crate::render::widgets::icon_button::IconButton;
+
+const ACTIVE_COLOR: (f64, f64, f64) = (1.0, 0.9, 1.0);
+/// tap areas of the tabs span the bar from the bottom of the screen
+const HEIGHT: f64 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NavTab {
+    Home,
+    Records,
+    Stats,
+}
+
+impl NavTab {
+    const ALL: [NavTab; 3] = [NavTab::Home, NavTab::Records, NavTab::Stats];
+
+    /// (icon, caption, icon left, tap area left and width)
+    fn layout(self) -> (&'static str, &'static str, f64, f64, f64) {
+        match self {
+            NavTab::Home => ("home", "Home", 0.2, 0.0, 0.33),
+            NavTab::Records => ("records", "Records", 0.45, 0.33, 0.33),
+            NavTab::Stats => ("stats", "Stats", 0.715, 0.66, 0.34),
+        }
+    }
+
+    /// Icon color when the tab isn't active, the color of its screen
+    fn color(self) -> (f64, f64, f64) {
+        match self {
+            NavTab::Home => (0.4, 0.2, 0.6),
+            NavTab::Records => (0.6, 0.8, 0.2),
+            NavTab::Stats => (0.4, 0.5, 0.9),
+        }
+    }
+}
+
+/// Home, Records and Stats tabs at the bottom of the main screens
+pub struct NavBar {
+    active: NavTab,
+    items: Container<NavTab>,
+    item_ids: Vec<WidgetId<IconButton>>,
+
+    /// start of the slide-away animation, the bar takes no taps after it
+    slide: Option<Instant>,
+}
+
+impl NavBar {
+    pub fn new(gl: Arc<gl::Gl>, active: NavTab) -> Self {
+        let font = get_font("queensides").unwrap();
+
+        let mut items = Container::new();
+        let item_ids = NavTab::ALL.iter().map(|tab| {
+            let (image, caption, icon_left, area_left, area_width) = tab.layout();
+            let color = if *tab == active { ACTIVE_COLOR } else { tab.color() };
+
+            let icon = Image::new(get_image(image).unwrap(),
+                                  FixedPosition::new().bottom(0.12).height(0.08).left(icon_left), Some(color));
+            let (left, _, width, _) = icon.bounds();
+            let caption = TextBox::new(gl.clone(), font.clone(), caption.to_string(), ((left + width / 2.0) as f32, 0.068), 0.45, 1)
+                .layout(TextLayout::new().align(Align::Center));
+            let area = FreePosition::new().left(area_left).bottom(0.0).width(area_width).height(HEIGHT);
+            items.add_tappable(IconButton::new(icon, area).caption(caption), *tab)
+        }).collect();
+
+        NavBar {
+            active,
+            items,
+            item_ids,
+
+            slide: None,
+        }
+    }
+
+    /// Command for a tap on the bar, None when the tap missed it
+    pub fn press(&self, pos: (f64, f64)) -> Option<ScreenManagementCmd> {
+        if self.slide.is_some() {
+            return None;
+        }
+        let tab = self.items.press(pos)?;
+
+        Some(if tab == self.active {
+            ScreenManagementCmd::None
+        }
+        else {
+            ScreenManagementCmd::SwitchTab(tab)
+        })
+    }
+
+    /// Area covered by the bar, taps in it don't reach the screen under the bar
+    pub fn bounds(&self) -> Bounds {
+        (0.0, 0.0, 1.0, HEIGHT)
+    }
+
+    /// Moves the tabs down out of the screen one after another
+    pub fn slide_away(&mut self) {
+        if self.slide.is_none() {
+            self.slide = Some(clock::now());
+        }
+    }
+
+    pub fn is_sliding(&self) -> bool {
+        self.slide.is_some()
+    }
+
+    pub fn draw(&mut self, texture_id: u32) {
+        if let Some(start) = self.slide {
+            let elapsed = (clock::elapsed(start).as_secs_f64() * 2.0).clamp(0.0, 3.5);
+            for (i, id) in self.item_ids.iter().enumerate() {
+                let offset = (elapsed - 0.5 * i as f64).max(0.0).cos() * 0.2 - 0.2;
+                self.items.get_mut(*id).set_y_offset(offset);
+            }
+        }
+
+        self.items.draw(texture_id);
+    }
+}
@@ -0,0 +1,36 @@
+use crate::render::objects::r#box::Squad;
+use crate::render::utils::position::FreePosition;
+use crate::render::widgets::{Bounds, Widget};
+
+/// Colored rectangle: background of dialogs and cards
+pub struct Panel {
+    squad: Squad,
+}
+
+impl Panel {
+    pub fn new(squad: Squad) -> Self {
+        Panel {
+            squad,
+        }
+    }
+
+    pub fn set_color(&mut self, color: (f32, f32, f32)) {
+        self.squad.set_color(color);
+    }
+
+    pub fn set_pos(&mut self, pos: FreePosition) {
+        self.squad.set_pos(pos);
+    }
+}
+
+impl Widget for Panel {
+    fn bounds(&self) -> Bounds {
+        self.squad.bounds()
+    }
+    fn set_y_offset(&mut self, offset: f64) {
+        self.squad.set_pos_y_offset(offset);
+    }
+    fn draw(&mut self, texture_id: u32) {
+        self.squad.draw(texture_id);
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+use log::error;
+use crate::format::{METERS_PER_KM, METERS_PER_MILE};
+use crate::storage::get_storage;
+use crate::tracking::autopause::AutoPauseConfig;
+use crate::tracking::splits::SplitsConfig;
+use crate::tracking::validation::ValidationConfig;
+use crate::tracking::TrackingConfig;
+
+const METERS_PER_FOOT: f64 = 0.3048;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum UnitSystem {
+    /// meters, kilometers, min/km, km/h
+    #[default]
+    Metric,
+    /// feet, miles, min/mi, mph
+    Imperial,
+}
+
+impl UnitSystem {
+    pub fn toggled(self) -> Self {
+        match self {
+            UnitSystem::Metric => UnitSystem::Imperial,
+            UnitSystem::Imperial => UnitSystem::Metric,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "metric",
+            UnitSystem::Imperial => "imperial",
+        }
+    }
+
+    /// Meters in a kilometer or a mile: unit of distances, paces and splits
+    pub fn long_unit(self) -> f64 {
+        match self {
+            UnitSystem::Metric => METERS_PER_KM,
+            UnitSystem::Imperial => METERS_PER_MILE,
+        }
+    }
+
+    /// Meters in a meter or a foot: unit of accuracy, elevation and short distances
+    pub fn short_unit(self) -> f64 {
+        match self {
+            UnitSystem::Metric => 1.0,
+            UnitSystem::Imperial => METERS_PER_FOOT,
+        }
+    }
+
+    pub fn long_label(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "km",
+            UnitSystem::Imperial => "mi",
+        }
+    }
+
+    pub fn short_label(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "m",
+            UnitSystem::Imperial => "ft",
+        }
+    }
+
+    pub fn pace_label(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "min/km",
+            UnitSystem::Imperial => "min/mi",
+        }
+    }
+
+    pub fn speed_label(self) -> &'static str {
+        match self {
+            UnitSystem::Metric => "km/h",
+            UnitSystem::Imperial => "mph",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Theme {
+    /// Every screen has its own bright background
+    #[default]
+    Colorful,
+    /// Same hues, dimmed
+    Dark,
+}
+
+impl Theme {
+    pub fn toggled(self) -> Self {
+        match self {
+            Theme::Colorful => Theme::Dark,
+            Theme::Dark => Theme::Colorful,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Colorful => "colorful",
+            Theme::Dark => "dark",
+        }
+    }
+
+    /// Background of a screen designed with `color`
+    pub fn background(self, color: (f32, f32, f32)) -> (f32, f32, f32) {
+        match self {
+            Theme::Colorful => color,
+            Theme::Dark => (color.0 * 0.25, color.1 * 0.25, color.2 * 0.25),
+        }
+    }
+}
+
+/// User preferences, stored in `settings.json` in the data directory.
+/// Missing fields take defaults, so older files keep loading.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub units: UnitSystem,
+    /// Fixes at least this precise start the training, meters
+    pub accuracy_threshold: f64,
+    /// Seconds of fixes ignored after GPS comes online
+    pub warmup: f64,
+    pub auto_pause: bool,
+    pub theme: Theme,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let validation = ValidationConfig::default();
+        Settings {
+            units: UnitSystem::default(),
+            accuracy_threshold: validation.good_accuracy,
+            warmup: validation.warmup,
+            auto_pause: AutoPauseConfig::default().enabled,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Pipeline tunables that depend on the preferences
+    pub fn tracking_config(&self) -> TrackingConfig {
+        TrackingConfig {
+            validation: ValidationConfig {
+                good_accuracy: self.accuracy_threshold,
+                warmup: self.warmup,
+                ..ValidationConfig::default()
+            },
+            autopause: AutoPauseConfig {
+                enabled: self.auto_pause,
+                ..AutoPauseConfig::default()
+            },
+            splits: SplitsConfig {
+                split_distance: self.units.long_unit(),
+            },
+            ..TrackingConfig::default()
+        }
+    }
+}
+
+lazy_static!(
+    static ref SETTINGS: Mutex<Settings> = Mutex::new(Settings::default());
+);
+
+pub fn settings() -> Settings {
+    SETTINGS.lock().unwrap().clone()
+}
+
+/// Replaces the settings without saving, used when they are loaded
+pub fn set_settings(settings: Settings) {
+    *SETTINGS.lock().unwrap() = settings;
+}
+
+/// Changes the settings and saves them if the storage is available
+pub fn update_settings(f: impl FnOnce(&mut Settings)) {
+    let mut settings = SETTINGS.lock().unwrap();
+    f(&mut settings);
+
+    if let Some(storage) = get_storage() {
+        if let Err(err) = storage.save_settings(&settings) {
+            error!("[Settings] Failed to save settings: {}", err);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::{info, warn};
+use serde_json::Value;
+use crate::formats::gpx::write_gpx;
+use crate::render::screens::records::{Record, Records};
+use crate::settings::Settings;
+use crate::track::{EncodedTrack, Track};
+
+/// Current version of the on-disk format. Bump it together with a new entry in `MIGRATIONS`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+const RECORDS_FILE: &str = "records.json";
+const SETTINGS_FILE: &str = "settings.json";
+const TRACKS_DIR: &str = "tracks";
+const EXPORTS_DIR: &str = "exports";
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// File was written by a newer version of the app
+    UnsupportedVersion(u32),
+    Malformed(&'static str),
+    /// Records file exists but wasn't loaded, saving would lose its records
+    RecordsNotLoaded,
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Io(err) => write!(f, "io error: {}", err),
+            StorageError::Json(err) => write!(f, "json error: {}", err),
+            StorageError::UnsupportedVersion(v) => write!(f, "unsupported schema version {} (max supported {})", v, SCHEMA_VERSION),
+            StorageError::Malformed(what) => write!(f, "malformed file: {}", what),
+            StorageError::RecordsNotLoaded => write!(f, "records file was not loaded, not overwriting it"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl StorageError {
+    /// The file can't be read by any version of the app, so it's safe to move it away
+    pub fn is_corrupted(&self) -> bool {
+        matches!(self, StorageError::Json(_) | StorageError::Malformed(_))
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(value: std::io::Error) -> Self {
+        StorageError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(value: serde_json::Error) -> Self {
+        StorageError::Json(value)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RecordsFileRef<'a> {
+    version: u32,
+    records: &'a Records,
+}
+
+#[derive(serde::Deserialize)]
+struct RecordsFile {
+    #[allow(dead_code)]
+    version: u32,
+    records: Records,
+}
+
+/// `MIGRATIONS[i]` upgrades a document of version `i` to version `i + 1`
+const MIGRATIONS: [fn(Value) -> Result<Value, StorageError>; SCHEMA_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+];
+
+/// v0 is the bare `Records` object without any envelope
+fn migrate_v0_to_v1(doc: Value) -> Result<Value, StorageError> {
+    if !doc.is_object() {
+        return Err(StorageError::Malformed("records document is not an object"));
+    }
+    Ok(serde_json::json!({
+        "version": 1,
+        "records": doc,
+    }))
+}
+
+/// v2 gives every record an id to find its track file by
+fn migrate_v1_to_v2(mut doc: Value) -> Result<Value, StorageError> {
+    let records = doc.get_mut("records")
+        .and_then(|r| r.get_mut("records"))
+        .and_then(|r| r.as_array_mut())
+        .ok_or(StorageError::Malformed("records list is missing"))?;
+
+    for (i, record) in records.iter_mut().enumerate() {
+        let record = record.as_object_mut()
+            .ok_or(StorageError::Malformed("record is not an object"))?;
+        record.insert("id".to_string(), Value::from(i as u64 + 1));
+        record.insert("track_points".to_string(), Value::from(0));
+    }
+    doc["version"] = Value::from(2);
+    Ok(doc)
+}
+
+fn document_version(doc: &Value) -> Result<u32, StorageError> {
+    match doc.get("version") {
+        None => Ok(0),
+        Some(v) => v.as_u64()
+            .map(|v| v as u32)
+            .ok_or(StorageError::Malformed("version is not a number")),
+    }
+}
+
+fn migrate(mut doc: Value) -> Result<Value, StorageError> {
+    let mut version = document_version(&doc)?;
+    if version > SCHEMA_VERSION {
+        return Err(StorageError::UnsupportedVersion(version));
+    }
+    while version < SCHEMA_VERSION {
+        info!("[Storage] Migrating records from v{} to v{}", version, version + 1);
+        doc = MIGRATIONS[version as usize](doc)?;
+        version += 1;
+    }
+    Ok(doc)
+}
+
+/// Writes `data` to a temporary file next to `path` and renames it over `path`,
+/// so readers never see a half written file.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), StorageError> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp_path, path)?;
+
+    // persist the rename itself, not supported on every platform
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+pub struct Storage {
+    data_dir: PathBuf,
+    /// set while the existing records file failed to load
+    records_not_loaded: AtomicBool,
+}
+
+impl Storage {
+    pub fn new(data_dir: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let data_dir = data_dir.into();
+        std::fs::create_dir_all(&data_dir)?;
+        Ok(Storage {
+            data_dir,
+            records_not_loaded: AtomicBool::new(false),
+        })
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    fn records_path(&self) -> PathBuf {
+        self.data_dir.join(RECORDS_FILE)
+    }
+
+    /// Returns empty records if nothing was saved yet.
+    /// After an error records aren't saved until they're loaded or quarantined
+    pub fn load_records(&self) -> Result<Records, StorageError> {
+        let result = self.read_records();
+        self.records_not_loaded.store(result.is_err(), Ordering::Relaxed);
+        result
+    }
+
+    fn read_records(&self) -> Result<Records, StorageError> {
+        let file = match File::open(self.records_path()) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Records::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let doc: Value = serde_json::from_reader(std::io::BufReader::new(file))?;
+        let doc = migrate(doc)?;
+        let records_file: RecordsFile = serde_json::from_value(doc)?;
+        Ok(records_file.records)
+    }
+
+    pub fn save_records(&self, records: &Records) -> Result<(), StorageError> {
+        if self.records_not_loaded.load(Ordering::Relaxed) {
+            return Err(StorageError::RecordsNotLoaded);
+        }
+        let data = serde_json::to_vec(&RecordsFileRef {
+            version: SCHEMA_VERSION,
+            records,
+        })?;
+        write_atomic(&self.records_path(), &data)
+    }
+
+    fn settings_path(&self) -> PathBuf {
+        self.data_dir.join(SETTINGS_FILE)
+    }
+
+    /// Returns default settings if nothing was saved yet
+    pub fn load_settings(&self) -> Result<Settings, StorageError> {
+        let file = match File::open(self.settings_path()) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    pub fn save_settings(&self, settings: &Settings) -> Result<(), StorageError> {
+        let data = serde_json::to_vec_pretty(settings)?;
+        write_atomic(&self.settings_path(), &data)
+    }
+
+    fn track_path(&self, record_id: u64) -> PathBuf {
+        self.data_dir.join(TRACKS_DIR).join(format!("{}.json", record_id))
+    }
+
+    /// Tracks are kept out of `records.json` and read only when a single record is opened
+    pub fn load_track(&self, record_id: u64) -> Result<Track, StorageError> {
+        let file = match File::open(self.track_path(record_id)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Track::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let encoded: EncodedTrack = serde_json::from_reader(std::io::BufReader::new(file))?;
+        encoded.decode().ok_or(StorageError::Malformed("track columns have different lengths"))
+    }
+
+    /// Refused while the records file isn't loaded, the track file may belong to one of its records
+    pub fn save_track(&self, record_id: u64, track: &Track) -> Result<(), StorageError> {
+        if self.records_not_loaded.load(Ordering::Relaxed) {
+            return Err(StorageError::RecordsNotLoaded);
+        }
+        std::fs::create_dir_all(self.data_dir.join(TRACKS_DIR))?;
+        let data = serde_json::to_vec(&track.encode())?;
+        write_atomic(&self.track_path(record_id), &data)
+    }
+
+    /// Largest record id with a track file, 0 if there are none.
+    /// Tracks outlive a quarantined records file, so new ids start above it
+    pub fn last_track_id(&self) -> u64 {
+        let Ok(entries) = std::fs::read_dir(self.data_dir.join(TRACKS_DIR)) else {
+            return 0;
+        };
+        entries.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            path.file_stem()?.to_str()?.parse::<u64>().ok()
+        }).max().unwrap_or(0)
+    }
+
+    /// Writes the record with its track as GPX into the exports directory, returns the file path
+    pub fn export_gpx(&self, record: &Record) -> Result<PathBuf, StorageError> {
+        let track = self.load_track(record.id)?;
+        let dir = self.data_dir.join(EXPORTS_DIR);
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!("training-{}.gpx", record.id));
+        write_atomic(&path, write_gpx(record, &track).as_bytes())?;
+        info!("[Storage] Record {} exported to {}", record.id, path.display());
+        Ok(path)
+    }
+
+    /// Moves unreadable records file out of the way, so the next save doesn't overwrite it
+    pub fn quarantine_records(&self) -> Result<PathBuf, StorageError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let target = self.data_dir.join(format!("{}.broken-{}", RECORDS_FILE, now));
+        std::fs::rename(self.records_path(), &target)?;
+        self.records_not_loaded.store(false, Ordering::Relaxed);
+        warn!("[Storage] Unreadable records moved to {}", target.display());
+        Ok(target)
+    }
+}
+
+static STORAGE: OnceLock<Storage> = OnceLock::new();
+
+pub fn init_storage(data_dir: impl Into<PathBuf>) -> Result<&'static Storage, StorageError> {
+    if let Some(storage) = STORAGE.get() {
+        return Ok(storage);
+    }
+    let storage = Storage::new(data_dir)?;
+    Ok(STORAGE.get_or_init(|| storage))
+}
+
+pub fn get_storage() -> Option<&'static Storage> {
+    STORAGE.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::screens::active_training::GpsData;
+    use crate::render::screens::records::add_record;
+    use super::*;
+
+    /// Empty directory of the test under the system temp dir
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("panther-storage-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn v0_records() -> Value {
+        serde_json::json!({
+            "records": [
+                { "timestamp": 1000.0, "distance": 5000.0, "time": 1500.0, "speed": 3.33 },
+                { "timestamp": 2000.0, "distance": 3000.0, "time": 1000.0, "speed": 3.0 },
+            ],
+            "total_distance": 8000.0,
+            "total_time": 2500.0,
+            "avg_speed": 3.2,
+        })
+    }
+
+    #[test]
+    fn migrates_v0_to_current() {
+        let dir = temp_dir("migrate");
+        std::fs::write(dir.join(RECORDS_FILE), v0_records().to_string()).unwrap();
+
+        let storage = Storage::new(&dir).unwrap();
+        let records = storage.load_records().unwrap();
+        let ids: Vec<u64> = records.records().iter().map(|r| r.id).collect();
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(records.records()[0].track_points, 0);
+        assert_eq!(records.total_distance, 8000.0);
+
+        // saved in the current version
+        storage.save_records(&records).unwrap();
+        let doc: Value = serde_json::from_slice(&std::fs::read(dir.join(RECORDS_FILE)).unwrap()).unwrap();
+        assert_eq!(document_version(&doc).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn save_then_load() {
+        let dir = temp_dir("roundtrip");
+        let storage = Storage::new(&dir).unwrap();
+        assert!(storage.load_records().unwrap().records().is_empty());
+
+        let records: Records = serde_json::from_value(migrate(v0_records()).unwrap()["records"].clone()).unwrap();
+        storage.save_records(&records).unwrap();
+
+        let loaded = Storage::new(&dir).unwrap().load_records().unwrap();
+        assert_eq!(loaded.records().len(), 2);
+        assert_eq!(loaded.records()[1].distance, 3000.0);
+        assert_eq!(loaded.total_time, 2500.0);
+        assert!(!dir.join(format!("{}.tmp", RECORDS_FILE)).exists());
+    }
+
+    #[test]
+    fn stray_tmp_file_is_ignored() {
+        let dir = temp_dir("tmp");
+        let storage = Storage::new(&dir).unwrap();
+        let records: Records = serde_json::from_value(migrate(v0_records()).unwrap()["records"].clone()).unwrap();
+        storage.save_records(&records).unwrap();
+
+        // write interrupted before the rename
+        std::fs::write(dir.join(format!("{}.tmp", RECORDS_FILE)), b"{\"version\": 2, \"reco").unwrap();
+
+        let loaded = storage.load_records().unwrap();
+        assert_eq!(loaded.records().len(), 2);
+    }
+
+    #[test]
+    fn future_version_is_kept() {
+        let dir = temp_dir("future");
+        let data = serde_json::json!({ "version": SCHEMA_VERSION + 1, "records": {} }).to_string();
+        std::fs::write(dir.join(RECORDS_FILE), &data).unwrap();
+
+        let storage = Storage::new(&dir).unwrap();
+        assert!(matches!(storage.load_records(), Err(StorageError::UnsupportedVersion(v)) if v == SCHEMA_VERSION + 1));
+        assert!(matches!(storage.save_records(&Records::default()), Err(StorageError::RecordsNotLoaded)));
+        assert_eq!(std::fs::read_to_string(dir.join(RECORDS_FILE)).unwrap(), data);
+    }
+
+    /// Track file of a record from the records file that didn't load
+    fn write_old_track(dir: &Path) -> String {
+        std::fs::create_dir_all(dir.join(TRACKS_DIR)).unwrap();
+        let data = r#"{"segments":[{"lat":[1],"lon":[2],"acc":[3],"time":[4]}]}"#.to_string();
+        std::fs::write(dir.join(TRACKS_DIR).join("1.json"), &data).unwrap();
+        data
+    }
+
+    #[test]
+    fn new_record_keeps_tracks_of_future_version() {
+        let dir = temp_dir("future-tracks");
+        std::fs::write(dir.join(RECORDS_FILE), serde_json::json!({ "version": SCHEMA_VERSION + 1, "records": {} }).to_string()).unwrap();
+        let track = write_old_track(&dir);
+
+        let storage = Storage::new(&dir).unwrap();
+        assert!(storage.load_records().is_err());
+        assert!(matches!(storage.save_track(1, &Track::new()), Err(StorageError::RecordsNotLoaded)));
+
+        let mut records = Records::default();
+        let id = add_record(&mut records, Some(&storage), &GpsData::new(), 1000.0);
+        assert_eq!(id, 2);
+        assert_eq!(std::fs::read_to_string(dir.join(TRACKS_DIR).join("1.json")).unwrap(), track);
+        assert!(!dir.join(TRACKS_DIR).join("2.json").exists());
+    }
+
+    #[test]
+    fn new_record_keeps_tracks_of_quarantined_records() {
+        let dir = temp_dir("quarantine-tracks");
+        std::fs::write(dir.join(RECORDS_FILE), b"{\"version\": 2, \"reco").unwrap();
+        let track = write_old_track(&dir);
+
+        let storage = Storage::new(&dir).unwrap();
+        assert!(matches!(storage.load_records(), Err(err) if err.is_corrupted()));
+        storage.quarantine_records().unwrap();
+
+        let mut records = Records::default();
+        let id = add_record(&mut records, Some(&storage), &GpsData::new(), 1000.0);
+        assert_eq!(id, 2);
+        assert_eq!(std::fs::read_to_string(dir.join(TRACKS_DIR).join("1.json")).unwrap(), track);
+        assert!(dir.join(TRACKS_DIR).join("2.json").exists());
+        assert_eq!(storage.load_records().unwrap().records().len(), 1);
+    }
+}
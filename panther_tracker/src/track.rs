@@ -0,0 +1,328 @@
+use crate::tracking::validation::{RejectedFix, RejectReason};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocationMetric {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub accuracy: f64,
+    /// seconds since UNIX epoch
+    pub timestamp: f64,
+    /// meters, if the fix has it. Only differences are used, so the reference surface doesn't matter
+    pub altitude: Option<f64>,
+}
+
+/// Recorded route of a training, split into segments: a new segment is started after every pause.
+/// Raw fixes are kept in memory as is, on disk they are stored as `EncodedTrack`.
+#[derive(Clone, Debug, Default)]
+pub struct Track {
+    pub segments: Vec<Vec<LocationMetric>>,
+    /// Fixes dropped by validation, kept for debugging
+    pub rejected: Vec<RejectedFix>,
+}
+
+impl Track {
+    pub fn new() -> Self {
+        Track {
+            segments: Vec::new(),
+            rejected: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, metric: LocationMetric) {
+        match self.segments.last_mut() {
+            Some(segment) => segment.push(metric),
+            None => self.segments.push(vec![metric]),
+        }
+    }
+
+    /// Next pushed point goes to a new segment. Does nothing if the current one is empty.
+    pub fn start_segment(&mut self) {
+        if self.segments.last().map(|s| !s.is_empty()).unwrap_or(false) {
+            self.segments.push(Vec::new());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.iter().map(|s| s.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn last(&self) -> Option<&LocationMetric> {
+        self.segments.last().and_then(|s| s.last())
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &LocationMetric> {
+        self.segments.iter().flatten()
+    }
+
+    pub fn encode(&self) -> EncodedTrack {
+        EncodedTrack {
+            segments: self.segments.iter()
+                .filter(|s| !s.is_empty())
+                .map(|s| EncodedSegment::encode(s))
+                .collect(),
+            rejected: self.rejected.iter()
+                .map(|r| EncodedRejectedFix {
+                    lat: r.fix.latitude,
+                    lon: r.fix.longitude,
+                    acc: r.fix.accuracy,
+                    time: r.fix.timestamp,
+                    alt: r.fix.altitude,
+                    reason: r.reason,
+                })
+                .collect(),
+        }
+    }
+}
+
+const DEGREES_SCALE: f64 = 1e7; // ~1cm at the equator
+const ACCURACY_SCALE: f64 = 1e2; // cm
+const TIME_SCALE: f64 = 1e3; // ms
+const ALTITUDE_SCALE: f64 = 1e2; // cm
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct EncodedTrack {
+    segments: Vec<EncodedSegment>,
+    #[serde(default)]
+    rejected: Vec<EncodedRejectedFix>,
+}
+
+/// Rejected fixes are rare, so they are stored as is
+#[derive(serde::Serialize, serde::Deserialize)]
+struct EncodedRejectedFix {
+    lat: f64,
+    lon: f64,
+    acc: f64,
+    time: f64,
+    #[serde(default)]
+    alt: Option<f64>,
+    reason: RejectReason,
+}
+
+/// Every column holds the first value as is and then differences to the previous value
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct EncodedSegment {
+    lat: Vec<i64>,
+    lon: Vec<i64>,
+    acc: Vec<i64>,
+    time: Vec<i64>,
+    /// Empty if no point has altitude. Deltas are taken between present values only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    alt: Vec<Option<i64>>,
+}
+
+fn delta_encode(values: impl Iterator<Item = f64>, scale: f64) -> Vec<i64> {
+    let mut prev = 0;
+    values.map(|v| {
+        let v = (v * scale).round() as i64;
+        let delta = v - prev;
+        prev = v;
+        delta
+    }).collect()
+}
+
+fn delta_decode(deltas: &[i64], scale: f64) -> impl Iterator<Item = f64> + '_ {
+    let mut acc = 0;
+    deltas.iter().map(move |d| {
+        acc += d;
+        acc as f64 / scale
+    })
+}
+
+fn delta_encode_optional(values: impl Iterator<Item = Option<f64>>, scale: f64) -> Vec<Option<i64>> {
+    let mut prev = 0;
+    values.map(|v| {
+        v.map(|v| {
+            let v = (v * scale).round() as i64;
+            let delta = v - prev;
+            prev = v;
+            delta
+        })
+    }).collect()
+}
+
+fn delta_decode_optional(deltas: &[Option<i64>], scale: f64) -> impl Iterator<Item = Option<f64>> + '_ {
+    let mut acc = 0;
+    deltas.iter().map(move |d| {
+        d.map(|d| {
+            acc += d;
+            acc as f64 / scale
+        })
+    })
+}
+
+impl EncodedSegment {
+    fn encode(points: &[LocationMetric]) -> Self {
+        EncodedSegment {
+            lat: delta_encode(points.iter().map(|p| p.latitude), DEGREES_SCALE),
+            lon: delta_encode(points.iter().map(|p| p.longitude), DEGREES_SCALE),
+            acc: delta_encode(points.iter().map(|p| p.accuracy), ACCURACY_SCALE),
+            time: delta_encode(points.iter().map(|p| p.timestamp), TIME_SCALE),
+            alt: if points.iter().any(|p| p.altitude.is_some()) {
+                delta_encode_optional(points.iter().map(|p| p.altitude), ALTITUDE_SCALE)
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    fn decode(&self) -> Option<Vec<LocationMetric>> {
+        let len = self.lat.len();
+        if self.lon.len() != len || self.acc.len() != len || self.time.len() != len {
+            return None;
+        }
+        if !self.alt.is_empty() && self.alt.len() != len {
+            return None;
+        }
+
+        let altitudes = delta_decode_optional(&self.alt, ALTITUDE_SCALE)
+            .chain(std::iter::repeat(None));
+        let points = delta_decode(&self.lat, DEGREES_SCALE)
+            .zip(delta_decode(&self.lon, DEGREES_SCALE))
+            .zip(delta_decode(&self.acc, ACCURACY_SCALE))
+            .zip(delta_decode(&self.time, TIME_SCALE))
+            .zip(altitudes)
+            .map(|((((latitude, longitude), accuracy), timestamp), altitude)| LocationMetric {
+                latitude,
+                longitude,
+                accuracy,
+                timestamp,
+                altitude,
+            })
+            .collect();
+        Some(points)
+    }
+}
+
+impl EncodedTrack {
+    /// None if column lengths don't match
+    pub fn decode(&self) -> Option<Track> {
+        let segments = self.segments.iter()
+            .map(|s| s.decode())
+            .collect::<Option<Vec<_>>>()?;
+        let rejected = self.rejected.iter()
+            .map(|r| RejectedFix {
+                fix: LocationMetric {
+                    latitude: r.lat,
+                    longitude: r.lon,
+                    accuracy: r.acc,
+                    timestamp: r.time,
+                    altitude: r.alt,
+                },
+                reason: r.reason,
+            })
+            .collect();
+        Some(Track {
+            segments,
+            rejected,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric(latitude: f64, longitude: f64, timestamp: f64, altitude: Option<f64>) -> LocationMetric {
+        LocationMetric {
+            latitude,
+            longitude,
+            accuracy: 4.5,
+            timestamp,
+            altitude,
+        }
+    }
+
+    #[test]
+    fn roundtrip_keeps_segments() {
+        let mut track = Track::new();
+        track.push(metric(59.93863, 30.31413, 1712752205.25, Some(12.5)));
+        track.push(metric(59.93871, 30.31402, 1712752206.25, Some(12.75)));
+        track.start_segment();
+        track.push(metric(59.94012, 30.31105, 1712752400.0, None));
+        track.push(metric(59.94, 30.311, 1712752401.5, None));
+        track.push(metric(-33.8688, 151.2093, 1712752403.0, None));
+
+        let decoded = track.encode().decode().unwrap();
+        assert_eq!(decoded.segments, track.segments);
+    }
+
+    #[test]
+    fn empty_segments_are_dropped() {
+        let mut track = Track::new();
+        track.segments = vec![vec![], vec![metric(1.0, 2.0, 3.0, None)], vec![]];
+
+        let decoded = track.encode().decode().unwrap();
+        assert_eq!(decoded.segments, vec![vec![metric(1.0, 2.0, 3.0, None)]]);
+    }
+
+    #[test]
+    fn values_are_rounded_to_the_scale() {
+        let points = [
+            LocationMetric { latitude: 10.123456789, longitude: -20.00000004, accuracy: 3.456, timestamp: 1000.0004, altitude: Some(1.234) },
+            LocationMetric { latitude: 10.12345675, longitude: -20.00000006, accuracy: 3.0, timestamp: 1000.0006, altitude: Some(1.236) },
+        ];
+        let encoded = EncodedSegment::encode(&points);
+        assert_eq!(encoded.lat, [101234568, 0]);
+        assert_eq!(encoded.time, [1000000, 1]);
+
+        let decoded = encoded.decode().unwrap();
+        assert_eq!(decoded[0].latitude, 10.1234568);
+        assert_eq!(decoded[0].longitude, -20.0);
+        assert_eq!(decoded[0].accuracy, 3.46);
+        assert_eq!(decoded[0].timestamp, 1000.0);
+        assert_eq!(decoded[0].altitude, Some(1.23));
+        assert_eq!(decoded[1].latitude, 10.1234568);
+        assert_eq!(decoded[1].longitude, -20.0000001);
+        assert_eq!(decoded[1].timestamp, 1000.001);
+        assert_eq!(decoded[1].altitude, Some(1.24));
+    }
+
+    #[test]
+    fn altitude_deltas_skip_missing_values() {
+        let points = [
+            metric(1.0, 1.0, 1.0, None),
+            metric(1.0, 1.0, 2.0, Some(10.0)),
+            metric(1.0, 1.0, 3.0, None),
+            metric(1.0, 1.0, 4.0, Some(12.5)),
+            metric(1.0, 1.0, 5.0, Some(12.0)),
+        ];
+        let encoded = EncodedSegment::encode(&points);
+        assert_eq!(encoded.alt, [None, Some(1000), None, Some(250), Some(-50)]);
+        assert_eq!(encoded.decode().unwrap(), points);
+
+        // no altitude at all isn't stored
+        let points = [metric(1.0, 1.0, 1.0, None), metric(1.0, 1.0, 2.0, None)];
+        let encoded = EncodedSegment::encode(&points);
+        assert!(encoded.alt.is_empty());
+        assert_eq!(encoded.decode().unwrap(), points);
+    }
+
+    #[test]
+    fn mismatched_columns_are_rejected() {
+        let points = [metric(1.0, 1.0, 1.0, Some(1.0)), metric(2.0, 2.0, 2.0, Some(2.0))];
+
+        let mut encoded = EncodedSegment::encode(&points);
+        encoded.lon.pop();
+        assert!(encoded.decode().is_none());
+
+        let mut encoded = EncodedSegment::encode(&points);
+        encoded.time.push(1);
+        assert!(encoded.decode().is_none());
+
+        let mut encoded = EncodedSegment::encode(&points);
+        encoded.alt.pop();
+        assert!(encoded.decode().is_none());
+
+        let mut track = EncodedTrack {
+            segments: vec![EncodedSegment::encode(&points), EncodedSegment::encode(&points)],
+            rejected: vec![],
+        };
+        assert!(track.decode().is_some());
+        track.segments[1].acc.clear();
+        assert!(track.decode().is_none());
+    }
+}
@@ -0,0 +1,171 @@
+use log::info;
+
+#[derive(Clone, Debug)]
+pub struct AutoPauseConfig {
+    pub enabled: bool,
+    /// Below this smoothed speed the runner is considered standing, m/s
+    pub stop_speed: f64,
+    /// Above this speed the runner is moving again, m/s. Higher than `stop_speed`,
+    /// so noise around a single threshold doesn't toggle the state on every fix
+    pub resume_speed: f64,
+    /// How long the speed has to stay low before pausing, seconds
+    pub stop_delay: f64,
+    /// How long the speed has to stay high before resuming, seconds
+    pub resume_delay: f64,
+}
+
+impl Default for AutoPauseConfig {
+    fn default() -> Self {
+        AutoPauseConfig {
+            enabled: true,
+            stop_speed: 0.5,
+            resume_speed: 1.0,
+            stop_delay: 3.0,
+            resume_delay: 2.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MotionState {
+    Moving,
+    Stopped,
+}
+
+/// State change reported by `AutoPause::update`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionChange {
+    pub state: MotionState,
+    /// Timestamp of the first fix in the new state. The change is confirmed only after a delay,
+    /// the time between `since` and the confirming fix was accounted in the old state.
+    pub since: f64,
+}
+
+/// Detects stops (e.g. at traffic lights) from the smoothed speed
+pub struct AutoPause {
+    config: AutoPauseConfig,
+    state: MotionState,
+    /// Timestamp when the speed crossed the threshold towards the other state
+    candidate_since: Option<f64>,
+}
+
+impl AutoPause {
+    pub fn new(config: AutoPauseConfig) -> Self {
+        AutoPause {
+            config,
+            state: MotionState::Moving,
+            candidate_since: None,
+        }
+    }
+
+    pub fn config(&self) -> &AutoPauseConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> MotionState {
+        self.state
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == MotionState::Stopped
+    }
+
+    pub fn reset(&mut self) {
+        self.state = MotionState::Moving;
+        self.candidate_since = None;
+    }
+
+    /// Feeds the speed at `timestamp`, returns the change if the state has switched
+    pub fn update(&mut self, speed: f64, timestamp: f64) -> Option<MotionChange> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let (towards_other, delay, other) = match self.state {
+            MotionState::Moving => (speed < self.config.stop_speed, self.config.stop_delay, MotionState::Stopped),
+            MotionState::Stopped => (speed > self.config.resume_speed, self.config.resume_delay, MotionState::Moving),
+        };
+
+        if !towards_other {
+            self.candidate_since = None;
+            return None;
+        }
+
+        let since = *self.candidate_since.get_or_insert(timestamp);
+        if timestamp - since < delay {
+            return None;
+        }
+
+        self.state = other;
+        self.candidate_since = None;
+        info!("[AutoPause] {:?} since {}", other, since);
+        Some(MotionChange {
+            state: other,
+            since,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds the speeds one second apart starting at `start`, returns the changes
+    fn feed(autopause: &mut AutoPause, start: f64, speeds: &[f64]) -> Vec<MotionChange> {
+        speeds.iter().enumerate()
+            .filter_map(|(i, speed)| autopause.update(*speed, start + i as f64))
+            .collect()
+    }
+
+    #[test]
+    fn stops_after_the_delay() {
+        let mut autopause = AutoPause::new(AutoPauseConfig::default());
+        assert!(feed(&mut autopause, 0.0, &[3.0, 3.0, 0.2, 0.2, 0.2]).is_empty());
+        assert_eq!(autopause.state(), MotionState::Moving);
+
+        let changes = feed(&mut autopause, 5.0, &[0.2]);
+        assert_eq!(changes, [MotionChange { state: MotionState::Stopped, since: 2.0 }]);
+        assert!(autopause.is_paused());
+    }
+
+    #[test]
+    fn short_dips_are_ignored() {
+        let mut autopause = AutoPause::new(AutoPauseConfig::default());
+        // slow for two seconds at a time, the candidate is dropped in between
+        let changes = feed(&mut autopause, 0.0, &[0.2, 0.2, 0.2, 2.0, 0.2, 0.2, 0.2, 2.0, 0.2]);
+        assert!(changes.is_empty());
+        assert_eq!(autopause.state(), MotionState::Moving);
+    }
+
+    #[test]
+    fn resumes_above_the_resume_speed_only() {
+        let mut autopause = AutoPause::new(AutoPauseConfig::default());
+        feed(&mut autopause, 0.0, &[0.0; 4]);
+        assert!(autopause.is_paused());
+
+        // between the thresholds: still standing
+        assert!(feed(&mut autopause, 4.0, &[0.8; 10]).is_empty());
+        assert!(autopause.is_paused());
+
+        let changes = feed(&mut autopause, 14.0, &[1.5, 1.5, 1.5]);
+        assert_eq!(changes, [MotionChange { state: MotionState::Moving, since: 14.0 }]);
+        assert!(!autopause.is_paused());
+    }
+
+    #[test]
+    fn disabled_never_pauses() {
+        let mut autopause = AutoPause::new(AutoPauseConfig { enabled: false, ..AutoPauseConfig::default() });
+        assert!(feed(&mut autopause, 0.0, &[0.0; 20]).is_empty());
+        assert_eq!(autopause.state(), MotionState::Moving);
+    }
+
+    #[test]
+    fn reset_forgets_the_candidate() {
+        let mut autopause = AutoPause::new(AutoPauseConfig::default());
+        feed(&mut autopause, 0.0, &[0.0, 0.0]);
+        autopause.reset();
+        // the delay starts over
+        assert!(feed(&mut autopause, 2.0, &[0.0, 0.0, 0.0]).is_empty());
+        assert_eq!(feed(&mut autopause, 5.0, &[0.0]), [MotionChange { state: MotionState::Stopped, since: 2.0 }]);
+    }
+}
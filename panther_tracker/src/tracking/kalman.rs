@@ -0,0 +1,240 @@
+
+#[derive(Clone, Debug)]
+pub struct KalmanConfig {
+    /// Standard deviation of unmodelled acceleration, m/s^2. Higher values follow turns faster,
+    /// lower ones smooth more.
+    pub process_noise: f64,
+    /// Fixes with worse accuracy (m) are ignored completely
+    pub max_accuracy: f64,
+    /// Reported accuracy is never trusted beyond this (m), protects from zero accuracy fixes
+    pub min_accuracy: f64,
+    /// Uncertainty of the velocity of the first fix, m/s
+    pub initial_velocity_std: f64,
+}
+
+impl Default for KalmanConfig {
+    fn default() -> Self {
+        KalmanConfig {
+            process_noise: 0.5,
+            max_accuracy: 30.0,
+            min_accuracy: 1.0,
+            initial_velocity_std: 5.0,
+        }
+    }
+}
+
+/// Position and velocity along one axis, axes are independent for isotropic fixes
+#[derive(Clone, Debug)]
+struct AxisState {
+    pos: f64,
+    vel: f64,
+    // covariance [[pp, pv], [pv, vv]]
+    pp: f64,
+    pv: f64,
+    vv: f64,
+}
+
+impl AxisState {
+    fn new(pos: f64, pos_var: f64, vel_var: f64) -> Self {
+        AxisState {
+            pos,
+            vel: 0.0,
+            pp: pos_var,
+            pv: 0.0,
+            vv: vel_var,
+        }
+    }
+
+    fn predict(&mut self, dt: f64, accel_var: f64) {
+        self.pos += self.vel * dt;
+
+        // P = F P F^T + Q for F = [[1, dt], [0, 1]], Q = white noise acceleration
+        let dt2 = dt * dt;
+        let pp = self.pp + 2.0 * dt * self.pv + dt2 * self.vv + accel_var * dt2 * dt2 / 4.0;
+        let pv = self.pv + dt * self.vv + accel_var * dt2 * dt / 2.0;
+        let vv = self.vv + accel_var * dt2;
+        self.pp = pp;
+        self.pv = pv;
+        self.vv = vv;
+    }
+
+    fn correct(&mut self, measured: f64, measurement_var: f64) {
+        let innovation = measured - self.pos;
+        let s = self.pp + measurement_var;
+        let k_pos = self.pp / s;
+        let k_vel = self.pv / s;
+
+        self.pos += k_pos * innovation;
+        self.vel += k_vel * innovation;
+
+        let pp = (1.0 - k_pos) * self.pp;
+        let pv = (1.0 - k_pos) * self.pv;
+        let vv = self.vv - k_vel * self.pv;
+        self.pp = pp;
+        self.pv = pv;
+        self.vv = vv;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmoothedFix {
+    pub east: f64,
+    pub north: f64,
+    pub velocity_east: f64,
+    pub velocity_north: f64,
+    pub timestamp: f64,
+    /// Standard deviation of the smoothed position, m
+    pub accuracy: f64,
+}
+
+impl SmoothedFix {
+    pub fn speed(&self) -> f64 {
+        self.velocity_east.hypot(self.velocity_north)
+    }
+}
+
+/// Constant-velocity Kalman filter over positions in a local metric frame (see `geo::LocalFrame`).
+/// Fixes are weighted by their reported accuracy instead of being accepted or dropped as a whole.
+pub struct KalmanFilter {
+    config: KalmanConfig,
+    state: Option<(AxisState, AxisState)>,
+    last_timestamp: f64,
+}
+
+impl KalmanFilter {
+    pub fn new(config: KalmanConfig) -> Self {
+        KalmanFilter {
+            config,
+            state: None,
+            last_timestamp: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> &KalmanConfig {
+        &self.config
+    }
+
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+
+    /// Feeds a fix (meters in local frame), returns the new estimate.
+    /// None if the fix is too inaccurate to be used.
+    pub fn update(&mut self, east: f64, north: f64, accuracy: f64, timestamp: f64) -> Option<SmoothedFix> {
+        if accuracy.is_nan() || accuracy > self.config.max_accuracy {
+            return None;
+        }
+        let measurement_var = accuracy.max(self.config.min_accuracy).powi(2);
+
+        match &mut self.state {
+            None => {
+                let vel_var = self.config.initial_velocity_std.powi(2);
+                self.state = Some((AxisState::new(east, measurement_var, vel_var),
+                                   AxisState::new(north, measurement_var, vel_var)));
+            }
+            Some((x, y)) => {
+                let dt = timestamp - self.last_timestamp;
+                // out of order fixes are only used as a correction
+                if dt > 0.0 {
+                    let accel_var = self.config.process_noise.powi(2);
+                    x.predict(dt, accel_var);
+                    y.predict(dt, accel_var);
+                }
+                x.correct(east, measurement_var);
+                y.correct(north, measurement_var);
+            }
+        }
+        self.last_timestamp = self.last_timestamp.max(timestamp);
+
+        self.current()
+    }
+
+    pub fn current(&self) -> Option<SmoothedFix> {
+        self.state.as_ref().map(|(x, y)| SmoothedFix {
+            east: x.pos,
+            north: y.pos,
+            velocity_east: x.vel,
+            velocity_north: y.vel,
+            timestamp: self.last_timestamp,
+            accuracy: ((x.pp + y.pp) / 2.0).sqrt(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift64*, so the noisy tracks are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn uniform(&mut self) -> f64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        /// Standard normal, Box-Muller
+        fn normal(&mut self) -> f64 {
+            let u1 = self.uniform().max(f64::MIN_POSITIVE);
+            let u2 = self.uniform();
+            (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+        }
+    }
+
+    fn path_length(points: &[(f64, f64)]) -> f64 {
+        points.windows(2).map(|p| (p[1].0 - p[0].0).hypot(p[1].1 - p[0].1)).sum()
+    }
+
+    #[test]
+    fn smooths_noisy_straight_line() {
+        const SPEED: f64 = 3.0;
+        const SECONDS: usize = 600;
+        const NOISE: f64 = 10.0;
+
+        let mut rng = Rng(0x5EED_1234_ABCD_0001);
+        let direction = 30f64.to_radians();
+        let (east_step, north_step) = (SPEED * direction.sin(), SPEED * direction.cos());
+
+        let mut filter = KalmanFilter::new(KalmanConfig::default());
+        let mut raw = vec![];
+        let mut smoothed = vec![];
+        for t in 0..=SECONDS {
+            let east = east_step * t as f64 + rng.normal() * NOISE / std::f64::consts::SQRT_2;
+            let north = north_step * t as f64 + rng.normal() * NOISE / std::f64::consts::SQRT_2;
+            raw.push((east, north));
+
+            let fix = filter.update(east, north, NOISE, t as f64).unwrap();
+            smoothed.push((fix.east, fix.north));
+        }
+
+        let truth = SPEED * SECONDS as f64;
+        let raw_error = (path_length(&raw) - truth).abs();
+        let smoothed_error = (path_length(&smoothed) - truth).abs();
+        // noise of the raw fixes adds up to several times the true distance
+        assert!(smoothed_error * 4.0 < raw_error, "smoothed {} raw {}", smoothed_error, raw_error);
+
+        let last = filter.current().unwrap();
+        assert!((last.speed() - SPEED).abs() < 0.5, "{}", last.speed());
+        assert!(last.accuracy < NOISE);
+    }
+
+    #[test]
+    fn inaccurate_fixes_are_ignored() {
+        let mut filter = KalmanFilter::new(KalmanConfig::default());
+        let max_accuracy = filter.config().max_accuracy;
+
+        assert_eq!(filter.update(0.0, 0.0, max_accuracy + 1.0, 0.0), None);
+        assert_eq!(filter.current(), None);
+
+        let first = filter.update(0.0, 0.0, 5.0, 1.0).unwrap();
+        assert_eq!(filter.update(500.0, 500.0, max_accuracy * 2.0, 2.0), None);
+        assert_eq!(filter.update(500.0, 500.0, f64::NAN, 2.0), None);
+        assert_eq!(filter.current(), Some(first));
+
+        // exactly the limit is still used
+        assert!(filter.update(1.0, 1.0, max_accuracy, 3.0).is_some());
+    }
+}
@@ -0,0 +1,22 @@
+use crate::tracking::autopause::AutoPauseConfig;
+use crate::tracking::kalman::KalmanConfig;
+use crate::tracking::pace::PaceConfig;
+use crate::tracking::splits::SplitsConfig;
+use crate::tracking::validation::ValidationConfig;
+
+pub mod autopause;
+pub mod kalman;
+pub mod pace;
+pub mod provider;
+pub mod splits;
+pub mod validation;
+
+/// Tunables of the live GPS pipeline
+#[derive(Clone, Debug, Default)]
+pub struct TrackingConfig {
+    pub kalman: KalmanConfig,
+    pub validation: ValidationConfig,
+    pub autopause: AutoPauseConfig,
+    pub splits: SplitsConfig,
+    pub pace: PaceConfig,
+}
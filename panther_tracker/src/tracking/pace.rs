@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug)]
+pub struct PaceConfig {
+    /// Current speed is averaged over this many seconds of recent fixes
+    pub window: f64,
+    /// Shorter spans are too noisy to show anything
+    pub min_span: f64,
+}
+
+impl Default for PaceConfig {
+    fn default() -> Self {
+        PaceConfig {
+            window: 15.0,
+            min_span: 3.0,
+        }
+    }
+}
+
+/// Current speed from a sliding time window over the running totals of `GpsData`.
+/// Distance is taken from the totals, so auto-paused stretches never get in.
+pub struct PaceWindow {
+    config: PaceConfig,
+    /// (timestamp, total distance), oldest first
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl PaceWindow {
+    pub fn new(config: PaceConfig) -> Self {
+        PaceWindow {
+            config,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, timestamp: f64, total_distance: f64) {
+        self.samples.push_back((timestamp, total_distance));
+
+        // the newest sample older than the window is kept, so the window is always covered
+        while self.samples.len() > 2 && timestamp - self.samples[1].0 >= self.config.window {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Forgets the samples, after a pause or when the runner stopped
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Meters per second, None until the window spans `min_span`
+    pub fn speed(&self) -> Option<f64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let span = last.0 - first.0;
+        if span < self.config.min_span || span <= 0.0 {
+            return None;
+        }
+        Some((last.1 - first.1) / span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format;
+    use crate::settings::UnitSystem;
+    use super::*;
+
+    #[test]
+    fn empty_and_short_windows_have_no_speed() {
+        let mut window = PaceWindow::new(PaceConfig::default());
+        assert_eq!(window.speed(), None);
+        window.push(100.0, 0.0);
+        assert_eq!(window.speed(), None);
+        window.push(102.0, 6.0);
+        assert_eq!(window.speed(), None);
+        window.push(103.0, 9.0);
+        assert_eq!(window.speed(), Some(3.0));
+
+        window.reset();
+        assert_eq!(window.speed(), None);
+    }
+
+    #[test]
+    fn single_timestamp_doesnt_divide_by_zero() {
+        let mut window = PaceWindow::new(PaceConfig { window: 15.0, min_span: 0.0 });
+        window.push(100.0, 10.0);
+        assert_eq!(window.speed(), None);
+        window.push(100.0, 12.0);
+        assert_eq!(window.speed(), None);
+    }
+
+    #[test]
+    fn standing_still_shows_no_pace() {
+        let mut window = PaceWindow::new(PaceConfig::default());
+        for i in 0..10 {
+            window.push(i as f64, 250.0);
+        }
+        let speed = window.speed().unwrap();
+        assert_eq!(speed, 0.0);
+        assert_eq!(format::speed_pace(speed, UnitSystem::Metric), format::NO_VALUE);
+    }
+
+    #[test]
+    fn old_samples_are_evicted() {
+        let mut window = PaceWindow::new(PaceConfig::default());
+        // slow at first, then 4 m/s for longer than the window
+        for i in 0..=10 {
+            window.push(i as f64, i as f64);
+        }
+        for i in 1..=20 {
+            window.push(10.0 + i as f64, 10.0 + 4.0 * i as f64);
+        }
+        assert_eq!(window.speed(), Some(4.0));
+        // the newest sample older than the window stays
+        assert_eq!(window.samples.len(), 16);
+        assert_eq!(window.samples.front().unwrap().0, 15.0);
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+use jni::JNIEnv;
+use jni::objects::JClass;
+use jni::sys::jdouble;
+use log::{debug, info, warn};
+use crate::{ACTIVITY_OBJ, JNI_ENV};
+use crate::track::LocationMetric;
+use crate::tracking::provider::{LocationEvent, LocationProvider, PermissionState};
+
+/// Filled from the JNI callbacks of `LocationHelper`, drained by `AndroidLocationProvider::poll_events`
+static PENDING_EVENTS: Mutex<Vec<LocationEvent>> = Mutex::new(Vec::new());
+static PERMISSION_STATE: AtomicU8 = AtomicU8::new(PERMISSION_UNKNOWN);
+
+const PERMISSION_UNKNOWN: u8 = 0;
+const PERMISSION_REQUESTED: u8 = 1;
+const PERMISSION_GRANTED: u8 = 2;
+const PERMISSION_DENIED: u8 = 3;
+
+fn push_event(event: LocationEvent) {
+    PENDING_EVENTS.lock().unwrap().push(event);
+}
+
+/// Fixes come from `android.location.LocationManager` through `LocationHelper.java`
+pub struct AndroidLocationProvider;
+
+impl AndroidLocationProvider {
+    pub fn new() -> Self {
+        AndroidLocationProvider
+    }
+}
+
+impl Default for AndroidLocationProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocationProvider for AndroidLocationProvider {
+    fn start(&mut self) {
+        let env = JNI_ENV.lock().unwrap();
+        let mut env = unsafe { JNIEnv::from_raw(env as *mut _).unwrap() };
+        let activity_lock = ACTIVITY_OBJ.lock();
+        let activity = activity_lock.as_ref().unwrap();
+
+        // don't overwrite an answer that is already known
+        let _ = PERMISSION_STATE.compare_exchange(PERMISSION_UNKNOWN, PERMISSION_REQUESTED, Ordering::Relaxed, Ordering::Relaxed);
+
+        //check and request permissions, location updates are started by the activity once granted
+        env.call_method(activity, "checkAndRequestPermissions", "()V", &[])
+            .expect("Failed to call checkAndRequestPermissions");
+    }
+
+    fn stop(&mut self) {
+        let env = JNI_ENV.lock().unwrap();
+        let mut env = unsafe { JNIEnv::from_raw(env as *mut _).unwrap() };
+        let activity_lock = ACTIVITY_OBJ.lock();
+        let activity = activity_lock.as_ref().unwrap();
+
+        // get activity field locationManager
+        let location_helper_instance = env.get_field(activity, "locationHelper", "Lcom/skygrel/panther/LocationHelper;").unwrap().l().unwrap();
+
+        // Now call the stopLocationUpdates method
+        env.call_method(location_helper_instance, "stopLocationUpdates", "()V", &[])
+            .expect("Failed to call stopLocationUpdates");
+    }
+
+    fn permission(&self) -> PermissionState {
+        match PERMISSION_STATE.load(Ordering::Relaxed) {
+            PERMISSION_REQUESTED => PermissionState::Requested,
+            PERMISSION_GRANTED => PermissionState::Granted,
+            PERMISSION_DENIED => PermissionState::Denied,
+            _ => PermissionState::Unknown,
+        }
+    }
+
+    fn poll_events(&mut self) -> Vec<LocationEvent> {
+        std::mem::take(&mut *PENDING_EVENTS.lock().unwrap())
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onLocationUpdate(
+    _env: JNIEnv,
+    _class: JClass,
+    latitude: jdouble,
+    longitude: jdouble,
+    acc: jdouble,
+    timestamp: jdouble,
+    altitude: jdouble,
+) {
+    debug!("[Location] Fix at {}: lat {}, lon {}, alt {}, acc {}", timestamp, latitude, longitude, altitude, acc);
+    push_event(LocationEvent::Fix(LocationMetric {
+        latitude,
+        longitude,
+        accuracy: acc,
+        timestamp,
+        altitude: (!altitude.is_nan()).then_some(altitude),
+    }));
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onProviderEnabled(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    info!("GPS provider enabled!");
+    push_event(LocationEvent::ProviderEnabled);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onProviderDisabled(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    warn!("GPS provider disabled!");
+    push_event(LocationEvent::ProviderDisabled);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onPermissionDenied(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    info!("[Location] Permission denied");
+    PERMISSION_STATE.store(PERMISSION_DENIED, Ordering::Relaxed);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_skygrel_panther_LocationHelper_onPermissionGranted(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    info!("[Location] Permission granted");
+    PERMISSION_STATE.store(PERMISSION_GRANTED, Ordering::Relaxed);
+}
@@ -0,0 +1,98 @@
+use std::sync::Mutex;
+use std::time::Instant;
+use lazy_static::lazy_static;
+use log::warn;
+use crate::render::screens::active_training::{GPS_DATA, GpsData};
+use crate::track::LocationMetric;
+
+#[cfg(target_os = "android")]
+pub mod android;
+pub mod simulated;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PermissionState {
+    /// Not asked yet
+    Unknown,
+    Requested,
+    Granted,
+    Denied,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LocationEvent {
+    Fix(LocationMetric),
+    ProviderEnabled,
+    ProviderDisabled,
+}
+
+/// Source of location fixes. Fixes are buffered by the provider and taken with `poll_events`
+/// from the render loop, so providers never touch `GPS_DATA` themselves.
+pub trait LocationProvider: Send {
+    /// Asks for the permission if needed and starts delivering fixes once it is granted
+    fn start(&mut self);
+    fn stop(&mut self);
+    fn permission(&self) -> PermissionState;
+    /// Events received since the previous call
+    fn poll_events(&mut self) -> Vec<LocationEvent>;
+}
+
+lazy_static! {
+    static ref LOCATION_PROVIDER: Mutex<Option<Box<dyn LocationProvider>>> = Mutex::new(None);
+}
+
+pub fn set_location_provider(provider: Box<dyn LocationProvider>) {
+    *LOCATION_PROVIDER.lock().unwrap() = Some(provider);
+}
+
+/// Runs `f` with the installed provider, None if there is no provider
+pub fn with_location_provider<R>(f: impl FnOnce(&mut dyn LocationProvider) -> R) -> Option<R> {
+    let mut provider = LOCATION_PROVIDER.lock().unwrap();
+    match provider.as_mut() {
+        Some(provider) => Some(f(provider.as_mut())),
+        None => {
+            warn!("[Location] No location provider installed");
+            None
+        }
+    }
+}
+
+/// Polled every frame, so unlike `with_location_provider` stays silent without a provider
+pub fn location_permission() -> PermissionState {
+    LOCATION_PROVIDER.lock().unwrap()
+        .as_ref()
+        .map(|p| p.permission())
+        .unwrap_or(PermissionState::Unknown)
+}
+
+pub fn dispatch_event(gps_data: &mut GpsData, event: LocationEvent) {
+    match event {
+        LocationEvent::Fix(fix) => {
+            gps_data.update_location(fix);
+        }
+        LocationEvent::ProviderEnabled => {
+            gps_data.set_available_since(Some(Instant::now()));
+        }
+        LocationEvent::ProviderDisabled => {
+            gps_data.set_available_since(None);
+        }
+    }
+}
+
+/// Moves pending events of the provider into `GPS_DATA`, called once per frame
+pub fn pump_location_events() {
+    let events = {
+        let mut provider = LOCATION_PROVIDER.lock().unwrap();
+        match provider.as_mut() {
+            Some(provider) => provider.poll_events(),
+            None => return,
+        }
+    };
+    if events.is_empty() {
+        return;
+    }
+
+    let mut gps_data = GPS_DATA.lock().unwrap();
+    for event in events {
+        dispatch_event(&mut gps_data, event);
+    }
+}
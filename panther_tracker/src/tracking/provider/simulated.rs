@@ -0,0 +1,183 @@
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use log::info;
+use crate::formats::import::{ImportError, parse_track};
+use crate::geo;
+use crate::track::{LocationMetric, Track};
+use crate::tracking::provider::{LocationEvent, LocationProvider, PermissionState};
+
+/// Replays a prerecorded route, in real time or faster.
+/// Fix timestamps are shifted so the route starts at the moment `start` is called.
+pub struct SimulatedLocationProvider {
+    /// timestamps are seconds since the first fix of the route
+    route: Vec<LocationMetric>,
+    speed_factor: f64,
+    permission: PermissionState,
+    /// (real start, UNIX time of the start)
+    started: Option<(Instant, f64)>,
+    next: usize,
+    pending: Vec<LocationEvent>,
+}
+
+impl SimulatedLocationProvider {
+    /// `speed_factor` 1.0 replays the route in real time, 10.0 ten times faster
+    pub fn new(track: &Track, speed_factor: f64) -> Self {
+        let first_time = track.points().next().map(|p| p.timestamp).unwrap_or(0.0);
+        let route = track.points()
+            .map(|p| LocationMetric {
+                timestamp: p.timestamp - first_time,
+                ..p.clone()
+            })
+            .collect();
+
+        SimulatedLocationProvider {
+            route,
+            speed_factor: speed_factor.max(f64::EPSILON),
+            permission: PermissionState::Unknown,
+            started: None,
+            next: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Route from a GPX or TCX file
+    pub fn from_file(path: &Path, speed_factor: f64) -> Result<Self, ImportError> {
+        let text = std::fs::read_to_string(path)?;
+        let track = parse_track(&text)?;
+        info!("[Simulation] Loaded route of {} points from {}", track.len(), path.display());
+        Ok(Self::new(&track, speed_factor))
+    }
+
+    /// Route walking through `waypoints` (lat, lon) at a constant `speed` m/s,
+    /// with a fix every `interval` seconds
+    pub fn from_waypoints(waypoints: &[(f64, f64)], speed: f64, interval: f64, accuracy: f64, speed_factor: f64) -> Self {
+        let mut track = Track::new();
+        let step = speed * interval;
+        let mut time = 0.0;
+
+        if let Some(&(lat, lon)) = waypoints.first() {
+            track.push(LocationMetric { latitude: lat, longitude: lon, accuracy, timestamp: time, altitude: None });
+        }
+        // distance already walked past the last emitted fix
+        let mut carried = 0.0;
+        for leg in waypoints.windows(2) {
+            let ((lat1, lon1), (lat2, lon2)) = (leg[0], leg[1]);
+            let length = geo::haversine_distance(lat1, lon1, lat2, lon2);
+            let bearing = geo::initial_bearing(lat1, lon1, lat2, lon2);
+
+            let mut along = step - carried;
+            while step > 0.0 && along <= length {
+                let (latitude, longitude) = geo::destination_point(lat1, lon1, bearing, along);
+                time += interval;
+                track.push(LocationMetric { latitude, longitude, accuracy, timestamp: time, altitude: None });
+                along += step;
+            }
+            carried = length - (along - step);
+        }
+        Self::new(&track, speed_factor)
+    }
+
+    /// Makes `start` behave as if the user has already answered the permission dialog
+    pub fn with_permission(mut self, permission: PermissionState) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.route.len()
+    }
+}
+
+impl LocationProvider for SimulatedLocationProvider {
+    fn start(&mut self) {
+        if self.permission == PermissionState::Denied {
+            return;
+        }
+        self.permission = PermissionState::Granted;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+        self.started = Some((Instant::now(), now));
+        self.next = 0;
+        self.pending.push(LocationEvent::ProviderEnabled);
+        info!("[Simulation] Replaying {} fixes at x{}", self.route.len(), self.speed_factor);
+    }
+
+    fn stop(&mut self) {
+        if self.started.take().is_some() {
+            self.pending.push(LocationEvent::ProviderDisabled);
+        }
+    }
+
+    fn permission(&self) -> PermissionState {
+        self.permission
+    }
+
+    fn poll_events(&mut self) -> Vec<LocationEvent> {
+        let mut events = std::mem::take(&mut self.pending);
+        let Some((started_at, start_time)) = self.started else {
+            return events;
+        };
+
+        let route_time = Instant::now().duration_since(started_at).as_secs_f64() * self.speed_factor;
+        while let Some(fix) = self.route.get(self.next) {
+            if fix.timestamp > route_time {
+                break;
+            }
+            events.push(LocationEvent::Fix(LocationMetric {
+                timestamp: start_time + fix.timestamp,
+                ..fix.clone()
+            }));
+            self.next += 1;
+
+            if self.is_finished() {
+                info!("[Simulation] Route finished");
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::render::screens::active_training::{GPS_DATA, GpsData};
+    use crate::tracking::provider::{pump_location_events, set_location_provider, with_location_provider};
+    use crate::tracking::TrackingConfig;
+    use super::*;
+
+    #[test]
+    fn training_from_simulated_route() {
+        const SPEED: f64 = 3.0;
+        const SPEED_FACTOR: f64 = 1000.0;
+
+        // 600 m to the north, then 400 m to the east
+        let start = (59.9717, 30.3243);
+        let corner = geo::destination_point(start.0, start.1, 0.0, 600.0);
+        let end = geo::destination_point(corner.0, corner.1, 90.0, 400.0);
+        let provider = SimulatedLocationProvider::from_waypoints(&[start, corner, end], SPEED, 1.0, 4.0, SPEED_FACTOR)
+            .with_permission(PermissionState::Granted);
+        let route_len = provider.route.len();
+        let route_duration = provider.route.last().unwrap().timestamp;
+
+        let mut config = TrackingConfig::default();
+        config.validation.warmup = 0.0;
+        *GPS_DATA.lock().unwrap() = GpsData::with_config(config);
+        set_location_provider(Box::new(provider));
+        with_location_provider(|provider| provider.start());
+
+        let deadline = Instant::now() + Duration::from_secs_f64(route_duration / SPEED_FACTOR) + Duration::from_secs(5);
+        while GPS_DATA.lock().unwrap().track().len() < route_len && Instant::now() < deadline {
+            pump_location_events();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        with_location_provider(|provider| provider.stop());
+        pump_location_events();
+
+        let gps_data = GPS_DATA.lock().unwrap();
+        assert_eq!(gps_data.track().len(), route_len);
+        assert!((gps_data.moving_time() - route_duration).abs() < 1e-6, "{}", gps_data.moving_time());
+        // the last fix is short of the end, smoothing cuts the corner a bit
+        let distance = gps_data.total_distance();
+        assert!((distance - 1000.0).abs() < 20.0, "{}", distance);
+    }
+}
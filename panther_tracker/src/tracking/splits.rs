@@ -0,0 +1,251 @@
+use log::info;
+
+#[derive(Clone, Debug)]
+pub struct SplitsConfig {
+    /// Distance of an automatic split, meters
+    pub split_distance: f64,
+}
+
+impl Default for SplitsConfig {
+    fn default() -> Self {
+        SplitsConfig {
+            split_distance: 1000.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SplitKind {
+    /// Every `split_distance` meters
+    Distance,
+    /// Taken manually by the runner
+    Lap,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Split {
+    pub kind: SplitKind,
+    /// 1-based number among the splits of the same kind
+    pub number: u32,
+    /// meters
+    pub distance: f64,
+    /// moving time, seconds
+    pub time: f64,
+    /// total distance at the end of the split
+    pub end_distance: f64,
+    /// meters gained (positive) or lost, None if the fixes have no altitude
+    pub elevation_change: Option<f64>,
+}
+
+/// Totals at a split boundary
+#[derive(Clone, Copy, Debug, Default)]
+struct Mark {
+    distance: f64,
+    time: f64,
+    altitude: Option<f64>,
+}
+
+/// Cuts the training into distance splits and manual laps.
+/// Fed with the running totals of `GpsData` after every accepted fix.
+pub struct SplitTracker {
+    config: SplitsConfig,
+    splits: Vec<Split>,
+    laps: Vec<Split>,
+    split_start: Mark,
+    lap_start: Mark,
+    last: Mark,
+}
+
+impl SplitTracker {
+    pub fn new(config: SplitsConfig) -> Self {
+        SplitTracker {
+            config,
+            splits: Vec::new(),
+            laps: Vec::new(),
+            split_start: Mark::default(),
+            lap_start: Mark::default(),
+            last: Mark::default(),
+        }
+    }
+
+    pub fn splits(&self) -> &[Split] {
+        &self.splits
+    }
+
+    pub fn laps(&self) -> &[Split] {
+        &self.laps
+    }
+
+    fn split(kind: SplitKind, number: u32, start: &Mark, end: &Mark) -> Split {
+        Split {
+            kind,
+            number,
+            distance: end.distance - start.distance,
+            time: end.time - start.time,
+            end_distance: end.distance,
+            elevation_change: start.altitude.zip(end.altitude).map(|(start, end)| end - start),
+        }
+    }
+
+    /// Returns the splits completed since the previous update, usually none or one
+    pub fn update(&mut self, total_distance: f64, moving_time: f64, altitude: Option<f64>) -> Vec<Split> {
+        let current = Mark {
+            distance: total_distance,
+            time: moving_time,
+            // keep the last known altitude for fixes without one
+            altitude: altitude.or(self.last.altitude),
+        };
+        if self.split_start.altitude.is_none() {
+            self.split_start.altitude = current.altitude;
+        }
+        if self.lap_start.altitude.is_none() {
+            self.lap_start.altitude = current.altitude;
+        }
+
+        let mut completed = Vec::new();
+        if self.config.split_distance > 0.0 {
+            loop {
+                let boundary = (self.splits.len() + 1) as f64 * self.config.split_distance;
+                if current.distance < boundary {
+                    break;
+                }
+
+                // the boundary lies between two fixes, time is interpolated
+                let covered = current.distance - self.last.distance;
+                let fraction = if covered > 0.0 { (boundary - self.last.distance) / covered } else { 1.0 };
+                let end = Mark {
+                    distance: boundary,
+                    time: self.last.time + (current.time - self.last.time) * fraction.clamp(0.0, 1.0),
+                    altitude: current.altitude,
+                };
+
+                let split = Self::split(SplitKind::Distance, self.splits.len() as u32 + 1, &self.split_start, &end);
+                info!("[Splits] Split {}: {:.0}s, elevation {:?}", split.number, split.time, split.elevation_change);
+                self.splits.push(split.clone());
+                completed.push(split);
+                self.split_start = end;
+            }
+        }
+
+        self.last = current;
+        completed
+    }
+
+    /// Total distance and moving time at the latest split or lap boundary.
+    /// Totals corrected backwards shouldn't go below them, the splits are already taken
+    pub fn committed(&self) -> (f64, f64) {
+        (self.split_start.distance.max(self.lap_start.distance), self.split_start.time.max(self.lap_start.time))
+    }
+
+    /// Distance splits with the unfinished last one, for a finished training
+    pub fn final_splits(&self) -> Vec<Split> {
+        let mut splits = self.splits.clone();
+        if self.last.distance > self.split_start.distance {
+            splits.push(Self::split(SplitKind::Distance, splits.len() as u32 + 1, &self.split_start, &self.last));
+        }
+        splits
+    }
+
+    /// Laps with the unfinished last one, empty if no lap was taken
+    pub fn final_laps(&self) -> Vec<Split> {
+        let mut laps = self.laps.clone();
+        if !laps.is_empty() && self.last.distance > self.lap_start.distance {
+            laps.push(Self::split(SplitKind::Lap, laps.len() as u32 + 1, &self.lap_start, &self.last));
+        }
+        laps
+    }
+
+    /// Ends the current lap at the last update
+    pub fn lap(&mut self) -> Split {
+        let lap = Self::split(SplitKind::Lap, self.laps.len() as u32 + 1, &self.lap_start, &self.last);
+        info!("[Splits] Lap {}: {:.0}m in {:.0}s", lap.number, lap.distance, lap.time);
+        self.laps.push(lap.clone());
+        self.lap_start = self.last;
+        lap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> SplitTracker {
+        SplitTracker::new(SplitsConfig::default())
+    }
+
+    #[test]
+    fn boundary_between_two_fixes() {
+        let mut tracker = tracker();
+        assert!(tracker.update(0.0, 0.0, Some(10.0)).is_empty());
+        assert!(tracker.update(900.0, 300.0, Some(14.0)).is_empty());
+
+        let completed = tracker.update(1100.0, 360.0, Some(18.0));
+        assert_eq!(completed, [Split {
+            kind: SplitKind::Distance,
+            number: 1,
+            distance: 1000.0,
+            // interpolated halfway between the fixes
+            time: 330.0,
+            end_distance: 1000.0,
+            elevation_change: Some(8.0),
+        }]);
+        assert_eq!(tracker.splits(), completed);
+
+        // the boundary itself completes a split
+        assert_eq!(tracker.update(2000.0, 600.0, None).len(), 1);
+        assert_eq!(tracker.splits()[1].time, 270.0);
+    }
+
+    #[test]
+    fn several_splits_in_one_update() {
+        let mut tracker = tracker();
+        tracker.update(0.0, 0.0, None);
+        let completed = tracker.update(3500.0, 1400.0, None);
+        assert_eq!(completed.iter().map(|s| s.number).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(completed.iter().all(|s| s.distance == 1000.0 && (s.time - 400.0).abs() < 1e-9));
+        assert_eq!(completed[2].end_distance, 3000.0);
+        assert_eq!(completed[0].elevation_change, None);
+
+        // the unfinished last one
+        let splits = tracker.final_splits();
+        assert_eq!(splits.len(), 4);
+        assert_eq!((splits[3].distance, splits[3].time), (500.0, 200.0));
+    }
+
+    #[test]
+    fn pause_is_not_in_the_split() {
+        // totals stop growing while paused, the first fix after resuming adds nothing
+        let mut tracker = tracker();
+        tracker.update(0.0, 0.0, None);
+        tracker.update(900.0, 300.0, None);
+        tracker.update(900.0, 300.0, None);
+        let completed = tracker.update(1100.0, 340.0, None);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].time, 320.0);
+    }
+
+    #[test]
+    fn laps() {
+        let mut tracker = tracker();
+        assert!(tracker.final_laps().is_empty());
+
+        tracker.update(0.0, 0.0, Some(5.0));
+        tracker.update(400.0, 120.0, Some(7.0));
+        let lap = tracker.lap();
+        assert_eq!((lap.kind, lap.number, lap.distance, lap.time, lap.elevation_change), (SplitKind::Lap, 1, 400.0, 120.0, Some(2.0)));
+        // fixes without altitude keep the last known one
+        tracker.update(700.0, 200.0, None);
+
+        let laps = tracker.final_laps();
+        assert_eq!(laps.len(), 2);
+        assert_eq!((laps[1].number, laps[1].distance, laps[1].time, laps[1].elevation_change), (2, 300.0, 80.0, Some(0.0)));
+        assert_eq!(tracker.committed(), (400.0, 120.0));
+    }
+
+    #[test]
+    fn zero_split_distance_disables_splits() {
+        let mut tracker = SplitTracker::new(SplitsConfig { split_distance: 0.0 });
+        tracker.update(0.0, 0.0, None);
+        assert!(tracker.update(5000.0, 1500.0, None).is_empty());
+    }
+}
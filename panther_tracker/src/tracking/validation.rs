@@ -0,0 +1,220 @@
+use log::warn;
+use crate::geo;
+use crate::track::LocationMetric;
+
+#[derive(Clone, Debug)]
+pub struct ValidationConfig {
+    /// Fastest believable movement, m/s
+    pub max_speed: f64,
+    /// Fastest believable speed up between two fixes, m/s^2
+    pub max_acceleration: f64,
+    /// After this many rejections in a row the validator trusts the new position,
+    /// otherwise one real jump (e.g. after a tunnel) would block the rest of the training
+    pub max_consecutive_rejections: u32,
+    /// Fixes at least this precise, meters, count as good. Only a good fix starts the training.
+    pub good_accuracy: f64,
+    /// Seconds of fixes ignored after the provider comes online, the first ones are often way off
+    pub warmup: f64,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            max_speed: 12.0,
+            max_acceleration: 8.0,
+            max_consecutive_rejections: 5,
+            good_accuracy: 5.5,
+            warmup: 10.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RejectReason {
+    /// NaN or out of range coordinates
+    InvalidValue,
+    /// Same position and time as the previous fix
+    Duplicate,
+    /// Timestamp is not after the previous fix
+    NonMonotonicTime,
+    ImpossibleSpeed,
+    ImpossibleAcceleration,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RejectedFix {
+    pub fix: LocationMetric,
+    pub reason: RejectReason,
+}
+
+/// Sanity checks of incoming fixes against the last accepted one
+pub struct FixValidator {
+    config: ValidationConfig,
+    last: Option<LocationMetric>,
+    last_speed: f64,
+    rejections_in_row: u32,
+}
+
+impl FixValidator {
+    pub fn new(config: ValidationConfig) -> Self {
+        FixValidator {
+            config,
+            last: None,
+            last_speed: 0.0,
+            rejections_in_row: 0,
+        }
+    }
+
+    pub fn check(&mut self, fix: &LocationMetric) -> Result<(), RejectReason> {
+        let res = self.validate(fix);
+        match res {
+            Ok(speed) => {
+                self.last = Some(fix.clone());
+                self.last_speed = speed;
+                self.rejections_in_row = 0;
+                Ok(())
+            }
+            Err(reason) => {
+                warn!("[Validation] Fix rejected ({:?}): lat {}, lon {}, acc {}, time {}",
+                      reason, fix.latitude, fix.longitude, fix.accuracy, fix.timestamp);
+                Err(reason)
+            }
+        }
+    }
+
+    /// Returns lower bound of the speed since the previous fix
+    fn validate(&mut self, fix: &LocationMetric) -> Result<f64, RejectReason> {
+        if !fix.latitude.is_finite() || !fix.longitude.is_finite() || !fix.timestamp.is_finite()
+            || !(-90.0..=90.0).contains(&fix.latitude) || !(-180.0..=180.0).contains(&fix.longitude)
+            || fix.accuracy.is_nan() || fix.accuracy < 0.0 {
+            return Err(RejectReason::InvalidValue);
+        }
+
+        let Some(last) = &self.last else {
+            return Ok(0.0);
+        };
+
+        let dt = fix.timestamp - last.timestamp;
+        if dt <= 0.0 {
+            if dt == 0.0 && fix.latitude == last.latitude && fix.longitude == last.longitude {
+                return Err(RejectReason::Duplicate);
+            }
+            return Err(RejectReason::NonMonotonicTime);
+        }
+
+        // both fixes may be off by their accuracy, only the rest of the jump is suspicious
+        let distance = geo::haversine_distance(last.latitude, last.longitude, fix.latitude, fix.longitude);
+        let speed = (distance - fix.accuracy - last.accuracy).max(0.0) / dt;
+
+        let reason = if speed > self.config.max_speed {
+            Some(RejectReason::ImpossibleSpeed)
+        } else if (speed - self.last_speed) / dt > self.config.max_acceleration {
+            Some(RejectReason::ImpossibleAcceleration)
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => {
+                self.rejections_in_row += 1;
+                if self.rejections_in_row >= self.config.max_consecutive_rejections {
+                    warn!("[Validation] {} fixes rejected in a row, accepting the new position", self.rejections_in_row);
+                    // speed to the new position is unknown
+                    return Ok(0.0);
+                }
+                Err(reason)
+            }
+            None => Ok(speed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fix `north` meters north of the origin
+    fn fix(north: f64, timestamp: f64) -> LocationMetric {
+        LocationMetric {
+            latitude: 59.9 + north / 111_320.0,
+            longitude: 30.3,
+            accuracy: 3.0,
+            timestamp,
+            altitude: None,
+        }
+    }
+
+    fn new_validator() -> FixValidator {
+        FixValidator::new(ValidationConfig::default())
+    }
+
+    #[test]
+    fn invalid_values() {
+        let mut validator = new_validator();
+        for bad in [
+            LocationMetric { latitude: f64::NAN, ..fix(0.0, 0.0) },
+            LocationMetric { latitude: 90.5, ..fix(0.0, 0.0) },
+            LocationMetric { longitude: -180.5, ..fix(0.0, 0.0) },
+            LocationMetric { timestamp: f64::INFINITY, ..fix(0.0, 0.0) },
+            LocationMetric { accuracy: -1.0, ..fix(0.0, 0.0) },
+        ] {
+            assert_eq!(validator.check(&bad), Err(RejectReason::InvalidValue));
+        }
+        assert_eq!(validator.check(&fix(0.0, 0.0)), Ok(()));
+    }
+
+    #[test]
+    fn duplicates_and_time_going_back() {
+        let mut validator = new_validator();
+        assert_eq!(validator.check(&fix(0.0, 10.0)), Ok(()));
+        assert_eq!(validator.check(&fix(0.0, 10.0)), Err(RejectReason::Duplicate));
+        assert_eq!(validator.check(&fix(2.0, 10.0)), Err(RejectReason::NonMonotonicTime));
+        assert_eq!(validator.check(&fix(2.0, 9.0)), Err(RejectReason::NonMonotonicTime));
+        assert_eq!(validator.check(&fix(2.0, 11.0)), Ok(()));
+    }
+
+    #[test]
+    fn impossible_speed() {
+        let mut validator = new_validator();
+        assert_eq!(validator.check(&fix(0.0, 0.0)), Ok(()));
+        assert_eq!(validator.check(&fix(100.0, 1.0)), Err(RejectReason::ImpossibleSpeed));
+        // the jump is within the accuracy of both fixes
+        assert_eq!(validator.check(&fix(6.0, 1.0)), Ok(()));
+    }
+
+    #[test]
+    fn impossible_acceleration() {
+        let mut validator = new_validator();
+        assert_eq!(validator.check(&fix(0.0, 0.0)), Ok(()));
+        // 11 m/s is a believable speed, but not right from standing
+        assert_eq!(validator.check(&fix(17.0, 1.0)), Err(RejectReason::ImpossibleAcceleration));
+
+        // the same speed reached gradually: 4, 8, 11 and 11 m/s beyond the accuracy
+        let mut validator = new_validator();
+        let mut north = 0.0;
+        for (i, step) in [0.0, 10.0, 14.0, 17.0, 17.0].into_iter().enumerate() {
+            north += step;
+            assert_eq!(validator.check(&fix(north, i as f64)), Ok(()), "{}", i);
+        }
+    }
+
+    #[test]
+    fn accepts_after_consecutive_rejections() {
+        let mut validator = new_validator();
+        let max = ValidationConfig::default().max_consecutive_rejections;
+        assert_eq!(validator.check(&fix(0.0, 0.0)), Ok(()));
+
+        // the position jumped for real, e.g. out of a tunnel
+        for i in 1..max {
+            assert_eq!(validator.check(&fix(5000.0, i as f64)), Err(RejectReason::ImpossibleSpeed));
+        }
+        assert_eq!(validator.check(&fix(5000.0, max as f64)), Ok(()));
+        // fixes near the new position are fine
+        assert_eq!(validator.check(&fix(5003.0, max as f64 + 1.0)), Ok(()));
+
+        // the counter starts over
+        for i in 1..max {
+            assert_eq!(validator.check(&fix(0.0, (max + 1 + i) as f64)), Err(RejectReason::ImpossibleSpeed));
+        }
+    }
+}
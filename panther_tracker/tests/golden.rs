@@ -0,0 +1,61 @@
+// Compares every screen rendered offscreen with its golden image. Needs EGL, Mesa's llvmpipe is enough.
+//
+//   cargo test --target x86_64-unknown-linux-gnu --features headless --test golden
+//
+// Rendered frames and diffs of failed screens go to `screenshots-out`,
+// the `screenshots` bin updates the golden images.
+
+use std::path::Path;
+use image::RgbaImage;
+use panther::render::golden::{golden_dir, render_screens, DIMS};
+use panther::render::headless::{compare_images, HeadlessRenderer};
+
+/// llvmpipe versions round a bit differently
+const CHANNEL_TOLERANCE: u8 = 3;
+const MAX_MISMATCHED_FRACTION: f64 = 0.001;
+
+/// Error describing the mismatch, the frame and the diff are saved for inspection
+fn check_screen(name: &str, actual: &RgbaImage) -> Result<(), String> {
+    let golden_path = golden_dir().join(format!("{}.png", name));
+    let out_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("screenshots-out");
+    let save_failure = |diff: Option<&RgbaImage>| {
+        std::fs::create_dir_all(&out_dir).unwrap();
+        actual.save(out_dir.join(format!("{}-actual.png", name))).unwrap();
+        if let Some(diff) = diff {
+            diff.save(out_dir.join(format!("{}-diff.png", name))).unwrap();
+        }
+    };
+
+    let expected = match image::open(&golden_path) {
+        Ok(image) => image.to_rgba8(),
+        Err(err) => {
+            save_failure(None);
+            return Err(format!("can't open {}: {} (run the screenshots bin to create it)", golden_path.display(), err));
+        }
+    };
+
+    let Some(diff) = compare_images(actual, &expected, CHANNEL_TOLERANCE) else {
+        save_failure(None);
+        return Err(format!("size {:?} instead of {:?}", actual.dimensions(), expected.dimensions()));
+    };
+
+    let total = (actual.width() * actual.height()) as f64;
+    if diff.mismatched_pixels as f64 / total > MAX_MISMATCHED_FRACTION {
+        save_failure(Some(&diff.diff_image));
+        return Err(format!("{} pixels differ (max channel diff {})", diff.mismatched_pixels, diff.max_channel_diff));
+    }
+    Ok(())
+}
+
+#[test]
+fn screens_match_golden_images() {
+    let mut renderer = HeadlessRenderer::new(DIMS).expect("Failed to create headless renderer");
+
+    let mut failures = vec![];
+    render_screens(&mut renderer, |name, image| {
+        if let Err(err) = check_screen(name, &image) {
+            failures.push(format!("{}: {}", name, err));
+        }
+    });
+    assert!(failures.is_empty(), "screens differ from golden images:\n{}", failures.join("\n"));
+}